@@ -0,0 +1,51 @@
+//! Compare approximating a 2000x2000 image downscaled for a 400x100 terminal to the 256-colour palette
+//! with `closest_colour()` for every pixel and with lookups into a `build_colour_lut()` table.
+//!
+//! Run with `cargo bench --bench colour_lut`.
+
+
+extern crate image;
+extern crate imgcatr;
+
+use imgcatr::util::{ANSI_COLOURS_256, ANSI_256_FIXED_START, build_colour_lut, closest_colour};
+use imgcatr::ResizeMode;
+use imgcatr::ops::{image_resized_size, resize_image};
+use image::{DynamicImage, Rgb, RgbImage};
+use image::imageops::FilterType;
+use std::time::{Duration, Instant};
+use std::hint::black_box;
+
+
+static ITERATIONS: u32 = 20;
+
+
+fn main() {
+    let source = DynamicImage::ImageRgb8(RgbImage::from_fn(2000, 2000, |x, y| Rgb([(x % 256) as u8, (y % 256) as u8, ((x + y) % 256) as u8])));
+    let img = resize_image(&source, image_resized_size((2000, 2000), (400, 100), ResizeMode::Stretch).0, FilterType::Nearest).to_rgb8();
+    let colours = &ANSI_COLOURS_256[ANSI_256_FIXED_START..];
+
+    let scan = time(|| img.pixels().map(|&px| closest_colour(px, colours)).collect::<Vec<_>>());
+    let lut = time(|| {
+        let lut = build_colour_lut(colours);
+        img.pixels().map(|px| lut[(px[0] >> 3) as usize][(px[1] >> 3) as usize][(px[2] >> 3) as usize] as usize).collect::<Vec<_>>()
+    });
+
+    let lut_table = build_colour_lut(colours);
+    let mismatched = img.pixels()
+        .filter(|&&px| closest_colour(px, colours) != lut_table[(px[0] >> 3) as usize][(px[1] >> 3) as usize][(px[2] >> 3) as usize] as usize)
+        .count();
+
+    println!("{}x{} pixels, {} iterations", img.width(), img.height(), ITERATIONS);
+    println!("closest_colour(): {:?}/iter", scan);
+    println!("lookup table:     {:?}/iter, including building it ({:.2}x)", lut, scan.as_secs_f64() / lut.as_secs_f64());
+    println!("{} pixels ({:.2}%) approximated differently", mismatched, mismatched as f64 * 100.0 / (img.width() * img.height()) as f64);
+}
+
+fn time<T, F: Fn() -> T>(f: F) -> Duration {
+    black_box(f());
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f());
+    }
+    start.elapsed() / ITERATIONS
+}
@@ -0,0 +1,47 @@
+//! Compare `create_colourtable()` approximating a 2000x2000 image for a 400x100 terminal on one thread and on all of them.
+//!
+//! Run with `cargo bench --features rayon`.
+
+
+extern crate image;
+extern crate imgcatr;
+extern crate rayon;
+
+use imgcatr::util::{ANSI_COLOURS_256, ANSI_256_FIXED_START};
+use imgcatr::ResizeMode;
+use imgcatr::ops::{create_colourtable, image_resized_size, resize_image};
+use image::{DynamicImage, Rgb, RgbImage};
+use image::imageops::FilterType;
+use rayon::ThreadPoolBuilder;
+use std::time::{Duration, Instant};
+use std::hint::black_box;
+
+
+static ITERATIONS: u32 = 20;
+
+
+fn main() {
+    let source = DynamicImage::ImageRgb8(RgbImage::from_fn(2000, 2000, |x, y| Rgb([(x % 256) as u8, (y % 256) as u8, ((x + y) % 256) as u8])));
+    let img = resize_image(&source, image_resized_size((2000, 2000), (400, 100), ResizeMode::Stretch).0, FilterType::Nearest);
+    let colours = &ANSI_COLOURS_256[ANSI_256_FIXED_START..];
+
+    let serial = ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+    let serial = serial.install(|| time(|| create_colourtable(&img, colours, colours)));
+    let parallel = time(|| create_colourtable(&img, colours, colours));
+
+    println!("{}x{} cells, {} iterations", img.width(), img.height() / 2, ITERATIONS);
+    println!("serial:   {:?}/iter", serial);
+    println!("parallel: {:?}/iter ({} threads, {:.2}x)",
+             parallel,
+             rayon::current_num_threads(),
+             serial.as_secs_f64() / parallel.as_secs_f64());
+}
+
+fn time<T, F: Fn() -> T>(f: F) -> Duration {
+    black_box(f());
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f());
+    }
+    start.elapsed() / ITERATIONS
+}
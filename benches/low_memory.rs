@@ -0,0 +1,54 @@
+//! Compare the peak memory use of loading an 8000x6000 JPEG and PNG for an 80x24 terminal at full size
+//! and with `load_image_low_memory()`, each in a fresh process so their peaks don't mask each other.
+//!
+//! Run with `cargo bench --bench low_memory`. Peak memory is read from `/proc`, so only on Linux.
+
+
+extern crate image;
+extern crate imgcatr;
+
+use imgcatr::ops::{load_image_low_memory, load_image_with_fallback};
+use image::{ImageFormat, ImageOutputFormat, Rgb, RgbImage};
+use std::path::PathBuf;
+use std::process::Command;
+use std::{env, fs};
+
+
+static WIDTH: u32 = 8000;
+static HEIGHT: u32 = 6000;
+
+
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    if args.len() == 4 && args[1] == "load" {
+        return load(&args[2], PathBuf::from(&args[3]));
+    }
+
+    let source = RgbImage::from_fn(WIDTH, HEIGHT, |x, y| Rgb([(x % 256) as u8, (y % 256) as u8, ((x ^ y) % 256) as u8]));
+    for (ext, format) in [("jpg", ImageOutputFormat::Jpeg(80)), ("png", ImageOutputFormat::Png)] {
+        let path = env::temp_dir().join(format!("imgcatr-low-memory.{}", ext));
+        source.write_to(&mut fs::File::create(&path).unwrap(), format).unwrap();
+
+        println!("{}x{} {}, {} bytes", WIDTH, HEIGHT, ext, fs::metadata(&path).unwrap().len());
+        for mode in ["full", "low-memory"] {
+            let out = Command::new(env::current_exe().unwrap()).args(["load", mode]).arg(&path).output().unwrap();
+            print!("  {:<10} {}", mode, String::from_utf8_lossy(&out.stdout));
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}
+
+fn load(mode: &str, path: PathBuf) {
+    let file = (path.display().to_string(), path.clone());
+    let format = if path.extension().unwrap() == "png" { ImageFormat::Png } else { ImageFormat::Jpeg };
+    let (img, _) = match mode {
+        "full" => load_image_with_fallback(&file, format).unwrap(),
+        _ => load_image_low_memory(&file, format, (80, 48)).unwrap(),
+    };
+
+    let peak = fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| status.lines().find(|l| l.starts_with("VmHWM:")).map(|l| l["VmHWM:".len()..].trim().to_string()))
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("decoded to {}x{}, peak RSS {}", img.width(), img.height(), peak);
+}
@@ -1,4 +1,6 @@
+use self::super::CropRect;
 use std::io::Write;
+use std::{error, fmt};
 
 
 /// Enum representing all possible values the application can fail.
@@ -8,6 +10,28 @@ pub enum Error {
     GuessingFormatFailed(String),
     /// Failed to open image file.
     OpeningImageFailed(String),
+    /// Failed to decode the image, e.g. because it's truncated or corrupt.
+    DecodingImageFailed(String),
+    /// Failed to render a page of a PDF document.
+    RenderingPdfFailed(String),
+    /// A length-prefixed frame, by index, was cut short.
+    MalformedFrame(usize),
+    /// Tried to display a PDF document without the `pdf` feature.
+    PdfUnsupported(String),
+    /// Failed to open the output file.
+    OpeningOutputFailed(String),
+    /// An option was given an unusable value, as described.
+    InvalidOption(String),
+    /// The command-line arguments couldn't be parsed, with the message clap rendered for them.
+    ArgumentParsingFailed(String),
+    /// The crop rectangle doesn't lie within the image, of the specified size.
+    InvalidCrop(CropRect, (u32, u32)),
+    /// Failed to fetch an image from a URL, as described.
+    FetchFailed(String),
+    /// The terminal size couldn't be detected, and no size to display at was given.
+    TerminalSizeUnknown,
+    /// The `--palette` file couldn't be read or has a malformed line, as described.
+    InvalidPalette(String),
 }
 
 impl Error {
@@ -16,7 +40,7 @@ impl Error {
     /// # Examples
     ///
     /// ```
-    /// # use termimage::Error;
+    /// # use imgcatr::Error;
     /// # use std::iter::FromIterator;
     /// let mut out = Vec::new();
     /// Error::GuessingFormatFailed("not_image.rs".to_string()).print_error(&mut out);
@@ -25,8 +49,9 @@ impl Error {
     /// ```
     pub fn print_error<W: Write>(&self, err_out: &mut W) {
         match *self {
-            Error::GuessingFormatFailed(ref fname) => writeln!(err_out, "Failed to guess format of \"{}\".", fname).unwrap(),
-            Error::OpeningImageFailed(ref fname) => writeln!(err_out, "Failed to open image file \"{}\".", fname).unwrap(),
+            // Already rendered by clap, with its own trailing newline
+            Error::ArgumentParsingFailed(ref msg) => write!(err_out, "{}", msg).unwrap(),
+            _ => writeln!(err_out, "{}", self).unwrap(),
         }
     }
 
@@ -36,14 +61,80 @@ impl Error {
     ///
     /// ```
     /// # use std::process::exit;
-    /// # use termimage::Error;
+    /// # use imgcatr::{CropRect, Error};
     /// assert_eq!(Error::GuessingFormatFailed("".to_string()).exit_value(), 1);
     /// assert_eq!(Error::OpeningImageFailed("".to_string()).exit_value(), 2);
+    /// assert_eq!(Error::DecodingImageFailed("".to_string()).exit_value(), 3);
+    /// assert_eq!(Error::RenderingPdfFailed("".to_string()).exit_value(), 4);
+    /// assert_eq!(Error::MalformedFrame(0).exit_value(), 5);
+    /// assert_eq!(Error::PdfUnsupported("".to_string()).exit_value(), 6);
+    /// assert_eq!(Error::OpeningOutputFailed("".to_string()).exit_value(), 7);
+    /// assert_eq!(Error::InvalidOption("".to_string()).exit_value(), 8);
+    /// assert_eq!(Error::ArgumentParsingFailed("".to_string()).exit_value(), 9);
+    /// assert_eq!(Error::InvalidCrop(CropRect { x: 0, y: 0, width: 1, height: 1 }, (0, 0)).exit_value(), 10);
+    /// assert_eq!(Error::FetchFailed("".to_string()).exit_value(), 11);
+    /// assert_eq!(Error::TerminalSizeUnknown.exit_value(), 12);
+    /// assert_eq!(Error::InvalidPalette("".to_string()).exit_value(), 13);
     /// ```
     pub fn exit_value(&self) -> i32 {
         match *self {
             Error::GuessingFormatFailed(_) => 1,
             Error::OpeningImageFailed(_) => 2,
+            Error::DecodingImageFailed(_) => 3,
+            Error::RenderingPdfFailed(_) => 4,
+            Error::MalformedFrame(_) => 5,
+            Error::PdfUnsupported(_) => 6,
+            Error::OpeningOutputFailed(_) => 7,
+            Error::InvalidOption(_) => 8,
+            Error::ArgumentParsingFailed(_) => 9,
+            Error::InvalidCrop(..) => 10,
+            Error::FetchFailed(_) => 11,
+            Error::TerminalSizeUnknown => 12,
+            Error::InvalidPalette(_) => 13,
         }
     }
-}
\ No newline at end of file
+}
+
+impl fmt::Display for Error {
+    /// Describe the error as `print_error()` does, but without the trailing newline.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use imgcatr::Error;
+    /// fn open() -> Result<(), Box<dyn std::error::Error>> {
+    ///     Err(Error::OpeningImageFailed("cat.png".to_string()))?
+    /// }
+    ///
+    /// assert_eq!(open().unwrap_err().to_string(), "Failed to open image file \"cat.png\".");
+    /// assert_eq!(Error::InvalidOption("--size 0x0".to_string()).to_string(), "Invalid option: --size 0x0.");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::GuessingFormatFailed(ref fname) => write!(f, "Failed to guess format of \"{}\".", fname),
+            Error::OpeningImageFailed(ref fname) => write!(f, "Failed to open image file \"{}\".", fname),
+            Error::DecodingImageFailed(ref fname) => write!(f, "Failed to decode image \"{}\".", fname),
+            Error::RenderingPdfFailed(ref fname) => write!(f, "Failed to render PDF \"{}\".", fname),
+            Error::MalformedFrame(index) => write!(f, "Length-prefixed frame {} is truncated.", index),
+            Error::PdfUnsupported(ref fname) => write!(f, "Can't display PDF \"{}\": built without the pdf feature.", fname),
+            Error::OpeningOutputFailed(ref fname) => write!(f, "Failed to open output file \"{}\".", fname),
+            Error::InvalidOption(ref desc) => write!(f, "Invalid option: {}.", desc),
+            Error::ArgumentParsingFailed(ref msg) => f.write_str(msg.trim_end()),
+            Error::InvalidCrop(rect, (width, height)) => {
+                write!(f,
+                       "Crop rectangle {},{},{},{} doesn't fit in the {}x{} image.",
+                       rect.x,
+                       rect.y,
+                       rect.width,
+                       rect.height,
+                       width,
+                       height)
+            }
+            Error::FetchFailed(ref desc) => write!(f, "Failed to fetch {}.", desc),
+            Error::TerminalSizeUnknown => f.write_str("Couldn't detect the terminal size, pass --size or --scale."),
+            Error::InvalidPalette(ref desc) => write!(f, "Invalid palette: {}.", desc),
+        }
+    }
+}
+
+impl error::Error for Error {}
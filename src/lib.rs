@@ -8,4 +8,4 @@ pub mod ops;
 pub mod util;
 
 pub use error::Error;
-pub use options::{Options, AnsiOutputFormat};
\ No newline at end of file
+pub use options::{Options, OptionsBuilder, Anchor, AnsiOutputFormat, CropRect, ResizeMode, TermCaps, Theme, ToneMapOperator};
\ No newline at end of file
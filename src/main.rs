@@ -1,5 +1,6 @@
 #![allow(unused_imports)]
-use std::io::{BufWriter, Write, stdout, stderr};
+use std::io::{BufWriter, Cursor, IsTerminal, Read, Write, stdin, stdout, stderr};
+use std::path::Path;
 use std::process::exit;
 use image::GenericImageView;
 
@@ -9,36 +10,631 @@ fn main() {
 }
 
 fn actual_main() -> i32 {
-    if let Err(err) = result_main() {
-        err.print_error(&mut stderr());
-        err.exit_value()
-    } else {
-        0
+    match result_main() {
+        Ok(exit_value) => exit_value,
+        Err(err) => {
+            err.print_error(&mut stderr());
+            err.exit_value()
+        }
     }
 }
 
-fn result_main() -> Result<(), imgcatr::Error> {
+/// Display all the images, reporting the ones that fail without stopping, and get the exit value of the first one that did, or 0.
+fn result_main() -> Result<i32, imgcatr::Error> {
     let opts = imgcatr::Options::parse();
+    let opts = match opts.output_size {
+        Some(size) if opts.output.is_some() => imgcatr::Options { size, ..opts },
+        _ => opts,
+    };
 
-    let format = imgcatr::ops::guess_format(&opts.image)?;
-    let img = imgcatr::ops::load_image(&opts.image, format)?;
+    if opts.list_formats {
+        for format in imgcatr::ops::decodable_formats() {
+            println!("{:?}: {}", format, format.extensions_str().join(", "));
+        }
+        if cfg!(feature = "pdf") {
+            println!("Pdf: pdf");
+        }
+        return Ok(0);
+    }
+
+    let mut out: Box<dyn Write> = match opts.output {
+        // Nothing's rendered, so nothing's written either
+        _ if opts.dry_run => Box::new(std::io::sink()),
+        Some(ref path) => Box::new(BufWriter::new(imgcatr::ops::create_output(path, opts.output_timeout)?)),
+        None => Box::new(BufWriter::new(stdout())),
+    };
+
+    if opts.length_prefixed {
+        let mut input = stdin().lock();
+        let mut index = 0;
+        let mut limiter = imgcatr::ops::FrameLimiter::new(opts.max_fps);
+        while opts.count == 0 || index < opts.count {
+            let frame = match imgcatr::ops::read_length_prefixed(&mut input, index)? {
+                Some(frame) => frame,
+                None => break,
+            };
+            let name = format!("<stdin frame {}>", index);
+            let img = match opts.format {
+                Some(format) => imgcatr::ops::load_image_from_reader(Cursor::new(&frame), format, &name)?,
+                None => imgcatr::ops::load_image_from_memory(&frame, &name)?,
+            };
+            let img = transform_loaded(&opts, img)?;
+            limiter.wait();
+            render(&opts, &img, &mut out);
+            index += 1;
+        }
+        return Ok(0);
+    }
 
-    let img_s = imgcatr::ops::image_resized_size(img.dimensions(), opts.size, opts.preserve_aspect);
-    let resized = imgcatr::ops::resize_image(&img, img_s);
+    let count = if opts.count == 0 { opts.images.len() } else { opts.count };
+    let mut exit_value = 0;
+    let mut any_shown = false;
+    let mut shown = Vec::new();
+    for (image, &image_ansi) in opts.images.iter().zip(&opts.image_ansi_out).take(count) {
+        let image_opts = imgcatr::Options { ansi_out: image_ansi.or(opts.ansi_out), ..opts.clone() };
+        match load_image(&image_opts, image, &mut out) {
+            Ok(Some(img)) => {
+                if any_shown {
+                    writeln!(out).unwrap();
+                }
+                any_shown = true;
+                render(&image_opts, &img, &mut out);
+                if opts.keep_alive {
+                    shown.push((image_opts, img));
+                }
+            }
+            Ok(None) => any_shown = true,
+            Err(err) => {
+                out.flush().unwrap();
+                err.print_error(&mut stderr());
+                if exit_value == 0 {
+                    exit_value = err.exit_value();
+                }
+            }
+        }
+    }
+
+    if opts.keep_alive && !opts.dry_run && !shown.is_empty() {
+        if !stdout().is_terminal() {
+            eprintln!("Not writing to a terminal, so not waiting for it to be resized");
+            return Ok(exit_value);
+        }
+
+        let mut watcher = imgcatr::ops::ResizeWatcher::new();
+        let mut limiter = imgcatr::ops::FrameLimiter::new(opts.max_fps);
+        limiter.wait();
+        loop {
+            let dims = watcher.wait();
+            limiter.wait();
+            let size = imgcatr::util::default_output_size(dims).unwrap_or(opts.size);
+            if opts.verbose {
+                eprintln!("Terminal resized, redrawing at {}x{}", size.0, size.1);
+            }
+            if opts.ansi_out.is_some() {
+                write!(out, "{}", imgcatr::util::ANSI_CLEAR_SCREEN).unwrap();
+            }
+            for (i, (image_opts, img)) in shown.iter().enumerate() {
+                if i != 0 {
+                    writeln!(out).unwrap();
+                }
+                render(&imgcatr::Options { size, ..image_opts.clone() }, img, &mut out);
+            }
+        }
+    }
+
+    Ok(exit_value)
+}
+
+/// Load the specified image, rotated, flipped, and cropped as configured,
+/// or get `None` if it was an animated GIF or WebP and was played, or written to `out` as a GIF, already.
+fn load_image<W: Write>(opts: &imgcatr::Options, image: &(String, std::path::PathBuf), out: &mut W) -> Result<Option<image::DynamicImage>, imgcatr::Error> {
+    let img = if image.1 == Path::new("-") {
+        let mut buf = Vec::new();
+        stdin().lock().read_to_end(&mut buf).map_err(|_| imgcatr::Error::OpeningImageFailed(image.0.clone()))?;
+        let format = match opts.format {
+            Some(format) => format,
+            None => imgcatr::ops::guess_format_from_memory(&buf, &image.0)?,
+        };
+        if opts.verbose {
+            eprintln!("Image format: {:?}", format);
+        }
+        imgcatr::ops::load_image_from_reader(Cursor::new(&buf), format, &image.0)?
+    } else if imgcatr::ops::is_url(&image.1) {
+        let (buf, format) = imgcatr::ops::fetch_image(&image.0)?;
+        let format = match opts.format.or(format) {
+            Some(format) => format,
+            None => imgcatr::ops::guess_format_from_memory(&buf, &image.0)?,
+        };
+        if opts.verbose {
+            eprintln!("Fetched {} bytes, image format: {:?}", buf.len(), format);
+        }
+        imgcatr::ops::load_image_from_reader(Cursor::new(&buf), format, &image.0)?
+    } else if opts.format.is_none() && imgcatr::ops::is_pdf(image)? {
+        if opts.verbose {
+            eprintln!("Image format: PDF");
+        }
+        let page_size = match opts.scale {
+            Some(_) => None,
+            None => Some((opts.size.0, opts.size.1 * 2)),
+        };
+        imgcatr::ops::load_pdf_page(image, opts.frame.unwrap_or(0), page_size)?
+    } else {
+        let format = match opts.format {
+            Some(format) => format,
+            None => imgcatr::ops::guess_format(image)?,
+        };
+        if opts.verbose {
+            eprintln!("Image format: {:?}", format);
+        }
+        if let Some(index) = opts.frame {
+            let mut frames = imgcatr::ops::load_frames(image, format)?;
+            if !frames.is_empty() {
+                if index >= frames.len() {
+                    eprintln!("\"{}\" only has {} frames, displaying the last one", image.0, frames.len());
+                }
+                let frame = frames.swap_remove(index.min(frames.len() - 1));
+                return transform_loaded(opts, image::DynamicImage::ImageRgba8(frame.into_buffer())).map(Some);
+            } else if index != 0 {
+                eprintln!("\"{}\" isn't animated, displaying its only frame", image.0);
+            }
+        } else if matches!(format, image::ImageFormat::Gif | image::ImageFormat::WebP) && opts.images.len() == 1 && !opts.dry_run &&
+                  opts.output.as_ref().and_then(|path| path.extension()).is_some_and(|ext| ext.eq_ignore_ascii_case("gif")) {
+            let frames = imgcatr::ops::load_frames(image, format)?;
+            if frames.len() > 1 {
+                write_animation(opts, frames, out)?;
+                return Ok(None);
+            }
+        } else if matches!(format, image::ImageFormat::Gif | image::ImageFormat::WebP) && !opts.clipboard && !opts.palette_preview &&
+                  opts.crop.is_none() && opts.rotate.is_none() && !opts.flip_h && !opts.flip_v && opts.output.is_none() && !opts.dry_run && opts.images.len() == 1 && stdout().is_terminal() {
+            let mut frames = imgcatr::ops::load_frames(image, format)?;
+            if frames.len() > 1 {
+                if opts.ansi_out == Some(imgcatr::AnsiOutputFormat::Truecolor) {
+                    play_animation(opts, frames);
+                    return Ok(None);
+                }
+                eprintln!("Animations only play with truecolor output, displaying the first frame of \"{}\"", image.0);
+                return Ok(Some(image::DynamicImage::ImageRgba8(frames.swap_remove(0).into_buffer())));
+            }
+        }
+        // Turned sideways, the image's width will be what fits the terminal's height
+        let min_size = if matches!(opts.rotate, Some(90 | 270)) { (opts.size.1 * 2, opts.size.0) } else { (opts.size.0, opts.size.1 * 2) };
+        let (img, loaded_format) = match (opts.low_memory, opts.fast_decode, opts.scale) {
+            (true, _, None) => imgcatr::ops::load_image_low_memory(image, format, min_size)?,
+            (_, true, None) => imgcatr::ops::load_image_downscaled(image, format, min_size)?,
+            _ => imgcatr::ops::load_image_with_fallback(image, format)?,
+        };
+        if opts.verbose && loaded_format != format {
+            eprintln!("Failed to decode as {:?}, decoded as {:?} instead", format, loaded_format);
+        }
+        if loaded_format == image::ImageFormat::Jpeg && imgcatr::ops::is_cmyk_jpeg(image)? {
+            eprintln!("\"{}\" is a CMYK JPEG, converted to RGB without its colour profile, so its colours may be off", image.0);
+        }
+        img
+    };
+    transform_loaded(opts, img).map(Some)
+}
+
+/// Rotate, then flip, then crop, the loaded image as configured.
+fn transform_loaded(opts: &imgcatr::Options, img: image::DynamicImage) -> Result<image::DynamicImage, imgcatr::Error> {
+    if opts.verbose {
+        if let Some(degrees) = opts.rotate {
+            eprintln!("Rotating by {} degrees", degrees);
+        }
+    }
+    let mut img = match opts.rotate {
+        Some(90) => img.rotate90(),
+        Some(180) => img.rotate180(),
+        Some(270) => img.rotate270(),
+        _ => img,
+    };
+    // Flipped after rotating, so they mirror the image as it's displayed
+    if opts.flip_h {
+        img = img.fliph();
+    }
+    if opts.flip_v {
+        img = img.flipv();
+    }
+
+    match opts.crop {
+        Some(rect) => {
+            if opts.verbose {
+                eprintln!("Cropping to {}x{} at {},{}", rect.width, rect.height, rect.x, rect.y);
+            }
+            imgcatr::ops::crop_image(&img, rect)
+        }
+        None => Ok(img),
+    }
+}
+
+/// Play the animated GIF or WebP's frames in place, stepped and interpolated as configured, with the cursor hidden until it's done.
+fn play_animation(opts: &imgcatr::Options, frames: Vec<image::Frame>) {
+    let (frames, size) = size_animation(opts, frames);
+    if opts.verbose {
+        eprintln!("Playing {} frames", frames.len());
+    }
+
+    let _guard = imgcatr::ops::ResetGuard::new();
+    let mut out = BufWriter::new(stdout().lock());
+    write!(out, "{}", imgcatr::util::ANSI_HIDE_CURSOR).unwrap();
+    imgcatr::ops::write_ansi_animated(&mut out, &frames, size, opts.filter, opts.loop_count, opts.max_fps);
+}
+
+/// Write the animated GIF or WebP's frames to `out` as an animated GIF, sized as it would be played,
+/// and stepped, interpolated, rotated, flipped, and cropped as configured.
+fn write_animation<W: Write>(opts: &imgcatr::Options, frames: Vec<image::Frame>, out: &mut W) -> Result<(), imgcatr::Error> {
+    // Not logging the same transformations for every frame
+    let frame_opts = imgcatr::Options { verbose: false, ..opts.clone() };
+    let frames = frames.into_iter()
+        .map(|frame| {
+            let delay = frame.delay();
+            let img = transform_loaded(&frame_opts, image::DynamicImage::ImageRgba8(frame.into_buffer()))?;
+            Ok(image::Frame::from_parts(img.into_rgba8(), 0, 0, delay))
+        })
+        .collect::<Result<Vec<_>, imgcatr::Error>>()?;
+    let (frames, size) = size_animation(opts, frames);
+    if opts.verbose {
+        eprintln!("Writing {} frames as an animated GIF", frames.len());
+    }
+
+    imgcatr::ops::write_gif_animated(out, &frames, size, opts.filter, opts.loop_count);
+    out.flush().unwrap();
+    Ok(())
+}
+
+/// Step and interpolate the animation's frames as configured, crop them to fill the terminal if need be,
+/// and get them with the size to resize them to, half-block pixels as for `write_ansi_animated()`.
+fn size_animation(opts: &imgcatr::Options, frames: Vec<image::Frame>) -> (Vec<image::Frame>, (u32, u32)) {
+    let frames = imgcatr::ops::interpolate_frames(imgcatr::ops::step_frames(frames, opts.frame_step), opts.interpolate_frames);
+    let dims = frames[0].buffer().dimensions();
+    let (size, crop) = match opts.scale {
+        Some(scale) => (imgcatr::ops::image_scaled_size(dims, scale), None),
+        None => imgcatr::ops::subcell_resized_size(dims, opts.size, (1, 2), opts.cell_aspect, opts.resize_mode),
+    };
+    let size = match opts.max_size {
+        Some((max_w, max_h)) => imgcatr::ops::cap_size(size, (max_w, max_h * 2)),
+        None => size,
+    };
+    if opts.verbose {
+        eprintln!("Source size: {}x{}", dims.0, dims.1);
+        if let Some(rect) = crop {
+            eprintln!("Cropping to {}x{} at {},{} to fill the terminal", rect.width, rect.height, rect.x, rect.y);
+        }
+        eprintln!("Target size: {}x{}", size.0, size.1);
+    }
+    let frames = match crop {
+        Some(rect) => {
+            frames.into_iter()
+                .map(|frame| {
+                    let buffer = image::imageops::crop_imm(frame.buffer(), rect.x, rect.y, rect.width, rect.height).to_image();
+                    image::Frame::from_parts(buffer, 0, 0, frame.delay())
+                })
+                .collect()
+        }
+        None => frames,
+    };
+    (frames, size)
+}
+
+fn render<W: Write>(opts: &imgcatr::Options, img: &image::DynamicImage, out: &mut W) {
+    if opts.palette_preview {
+        return render_palette_preview(opts, img, out);
+    }
+
+    let tonemapped;
+    let img = if matches!(img.color(), image::ColorType::Rgb32F | image::ColorType::Rgba32F) {
+        if opts.verbose {
+            eprintln!("Tone-mapping with {:?} and exposure {}", opts.tonemap, opts.exposure);
+        }
+        tonemapped = imgcatr::ops::tonemap_hdr(img, opts.exposure, opts.tonemap);
+        &tonemapped
+    } else {
+        img
+    };
+
+    let bg_only = opts.bg_only && opts.ansi_out == Some(imgcatr::AnsiOutputFormat::Truecolor);
+    let sixel = opts.ansi_out == Some(imgcatr::AnsiOutputFormat::Sixel);
+    let pixels = sixel || opts.ansi_out == Some(imgcatr::AnsiOutputFormat::Kitty);
+    let subcells = opts.ansi_out.map_or((1, 2), imgcatr::AnsiOutputFormat::subcells);
+    // Each tile gets its share of the terminal
+    let (cols, rows) = opts.repeat.unwrap_or((1, 1));
+    let term_size = ((opts.size.0 / cols).max(1), (opts.size.1 / rows).max(1));
+    let (mut img_s, crop) = match opts.scale {
+        Some(scale) => {
+            let scaled = imgcatr::ops::image_scaled_size(img.dimensions(), scale);
+            ((scaled.0 * subcells.0, scaled.1 * subcells.1 / 2), None)
+        }
+        None if pixels => imgcatr::ops::pixel_resized_size(img.dimensions(), term_size, opts.caps.cell_pixels, opts.resize_mode),
+        None => imgcatr::ops::subcell_resized_size(img.dimensions(), term_size, subcells, opts.cell_aspect, opts.resize_mode),
+    };
+    if let Some((max_w, max_h)) = opts.max_size.map(|(max_w, max_h)| ((max_w / cols).max(1), (max_h / rows).max(1))) {
+        let (cell_w, cell_h) = if pixels {
+            opts.caps.cell_pixels.map_or((10, 20), |(w, h)| (w as u32, h as u32))
+        } else {
+            subcells
+        };
+        let capped = imgcatr::ops::cap_size(img_s, (max_w * cell_w, max_h * cell_h));
+        if opts.verbose && capped != img_s {
+            eprintln!("Capping the size from {}x{} to {}x{} for --max-size", img_s.0, img_s.1, capped.0, capped.1);
+        }
+        img_s = capped;
+    }
+    if bg_only {
+        img_s.1 = (img_s.1 / 2).max(1);
+    }
+    if sixel {
+        let clamped = opts.caps.clamp_sixel_size(img_s);
+        if clamped != img_s {
+            eprintln!("Shrinking the image from {}x{} to {}x{} to fit the terminal's largest sixel image", img_s.0, img_s.1, clamped.0, clamped.1);
+            img_s = clamped;
+        }
+    }
+    let pixels_per_row = if bg_only {
+        1
+    } else if pixels {
+        opts.caps.cell_pixels.map(|(_, h)| h as u32).unwrap_or(20)
+    } else {
+        subcells.1
+    };
+    if opts.verbose || opts.dry_run {
+        eprintln!("Source size: {}x{}", img.width(), img.height());
+        if let Some(rect) = crop {
+            eprintln!("Cropping to {}x{} at {},{} to fill the terminal", rect.width, rect.height, rect.x, rect.y);
+        }
+        if opts.dry_run {
+            eprintln!("Terminal size: {}x{}", opts.size.0, opts.size.1);
+        }
+        eprintln!("Target size: {}x{}", img_s.0, img_s.1);
+        match opts.ansi_out {
+            Some(ansi) => eprintln!("Output mode: {:?}", ansi),
+            None => eprintln!("Output mode: WinAPI console"),
+        }
+    }
+    if opts.dry_run {
+        return;
+    }
+    let cropped;
+    let img = match crop {
+        Some(rect) => {
+            cropped = img.crop_imm(rect.x, rect.y, rect.width, rect.height);
+            &cropped
+        }
+        None => img,
+    };
+    let mut resized = imgcatr::ops::resize_image(img, img_s, opts.filter);
+    if opts.repeat.is_some() {
+        if opts.verbose {
+            eprintln!("Tiling {}x{} times", cols, rows);
+        }
+        resized = imgcatr::ops::tile_image(&resized, cols, rows);
+    }
+    if opts.no_alpha && resized.color().has_alpha() {
+        resized = image::DynamicImage::ImageRgb8(resized.to_rgb8());
+    }
+    // These draw transparency themselves, by leaving it blank
+    let draws_alpha = matches!(opts.ansi_out,
+                               Some(imgcatr::AnsiOutputFormat::Sixel | imgcatr::AnsiOutputFormat::Kitty | imgcatr::AnsiOutputFormat::Braille |
+                                    imgcatr::AnsiOutputFormat::ASCII));
+    if resized.color().has_alpha() && (opts.background.is_some() || !draws_alpha) {
+        let bg = opts.background.or(opts.caps.bg).unwrap_or(opts.theme.background());
+        if opts.verbose {
+            eprintln!("Compositing transparency over {},{},{}", bg[0], bg[1], bg[2]);
+        }
+        resized = imgcatr::ops::flatten_on_background(&resized, bg);
+    }
+    resized = imgcatr::ops::adjust_image(&resized, opts.brightness, opts.contrast, opts.gamma);
+    if opts.grayscale {
+        resized = resized.grayscale();
+    }
+    if opts.invert {
+        resized.invert();
+    }
+    if opts.dither && opts.ansi_out == Some(imgcatr::AnsiOutputFormat::Truecolor) {
+        if opts.verbose {
+            eprintln!("Dithering with seed {}", opts.seed);
+        }
+        resized = imgcatr::ops::dither_noise(&resized, opts.seed);
+    } else if let Some((colours, lower_colours)) = opts.ansi_out.and_then(|ansi| palette_colours(ansi, opts)).filter(|_| opts.dither) {
+        if opts.interpolate_palette {
+            eprintln!("Dithering isn't supported with --interpolate-palette");
+        } else {
+            if opts.verbose {
+                eprintln!("Dithering with Floyd-Steinberg error diffusion");
+            }
+            resized = imgcatr::ops::dither_floyd_steinberg(&resized, colours, lower_colours);
+        }
+    }
+    let clipped = match opts.max_rows.and_then(|max_rows| imgcatr::ops::clip_rows(&resized, max_rows, pixels_per_row, opts.clip_centered)) {
+        Some(clipped) => {
+            if opts.verbose {
+                eprintln!("Clipped to {} rows", clipped.height() / pixels_per_row);
+            }
+            resized = clipped;
+            true
+        }
+        None => false,
+    };
+    let offset = if (opts.center || opts.anchor.is_some()) && opts.ansi_out.is_some() {
+        let cell_width = if pixels {
+            opts.caps.cell_pixels.map(|(w, _)| w as u32).unwrap_or(10)
+        } else {
+            subcells.0
+        };
+        let columns = term_size::dimensions().map(|(w, _)| w as u32).unwrap_or(opts.size.0);
+        let img_cells = (resized.width().div_ceil(cell_width), resized.height().div_ceil(pixels_per_row));
+        let offset = match opts.anchor {
+            Some(anchor) => imgcatr::ops::anchor_offset(img_cells, (columns, opts.size.1), anchor),
+            None => (imgcatr::ops::center_offset(img_cells.0, columns), 0),
+        };
+        if opts.verbose {
+            eprintln!("Placing with {} columns and {} lines of padding", offset.0, offset.1);
+        }
+        offset
+    } else {
+        if opts.center || opts.anchor.is_some() {
+            eprintln!("Placing the image isn't supported for WinAPI console output");
+        }
+        (0, 0)
+    };
+    if opts.accuracy {
+        if opts.interpolate_palette {
+            eprintln!("Colour error isn't measured with --interpolate-palette");
+        } else {
+            report_accuracy(&resized, opts.ansi_out, opts);
+        }
+    }
 
     match opts.ansi_out {
         Some(ansi) => {
-            let mut out = BufWriter::new(stdout().lock());
-            match ansi {
-                imgcatr::AnsiOutputFormat::Truecolor => imgcatr::ops::write_ansi_truecolor(&mut out, &resized),
-                imgcatr::AnsiOutputFormat::SimpleWhite => imgcatr::ops::write_ansi(&mut out, &resized, &imgcatr::util::ANSI_COLOURS_WHITE_BG),
-                imgcatr::AnsiOutputFormat::SimpleBlack => imgcatr::ops::write_ansi(&mut out, &resized, &imgcatr::util::ANSI_COLOURS_BLACK_BG),
-                imgcatr::AnsiOutputFormat::ASCII => imgcatr::ops::write_ascii(&resized),
+            let _guard = if opts.output.is_none() && stdout().is_terminal() {
+                Some(imgcatr::ops::ResetGuard::new())
+            } else {
+                None
+            };
+            if opts.clipboard && !matches!(ansi, imgcatr::AnsiOutputFormat::Sixel | imgcatr::AnsiOutputFormat::Kitty) {
+                let mut rendered = Vec::new();
+                write_offset_ansi_image(&mut rendered, &resized, ansi, opts, offset);
+                if clipped {
+                    writeln!(rendered, "{}", imgcatr::util::TRUNCATED_INDICATOR).unwrap();
+                }
+                let copied = imgcatr::ops::write_clipboard(out, &rendered);
+                if copied < rendered.len() {
+                    eprintln!("Output too big for the clipboard, copied only the first {} of {} bytes", copied, rendered.len());
+                }
+            } else {
+                if opts.clipboard {
+                    eprintln!("Copying {} output to the clipboard isn't supported, printing it instead", ansi.name());
+                }
+                write_offset_ansi_image(out, &resized, ansi, opts, offset);
+                if clipped {
+                    writeln!(out, "{}", imgcatr::util::TRUNCATED_INDICATOR).unwrap();
+                }
+            }
+            out.flush().unwrap();
+        }
+        None => {
+            imgcatr::ops::write_no_ansi(out, &resized);
+            if clipped {
+                writeln!(out, "{}", imgcatr::util::TRUNCATED_INDICATOR).unwrap();
             }
             out.flush().unwrap();
         }
-        None => imgcatr::ops::write_no_ansi(&resized),
     }
+}
 
-    Ok(())
+/// Render the image stacked once per output format up to the selected one, each under a label with its `--ansi` value,
+/// splitting the height between them, and skipping graphics protocols other than the selected one the terminal isn't known to support.
+fn render_palette_preview<W: Write>(opts: &imgcatr::Options, img: &image::DynamicImage, out: &mut W) {
+    let ansi = match opts.ansi_out {
+        Some(ansi) => ansi,
+        None => {
+            eprintln!("Previewing palettes needs ANSI output, rendering normally");
+            return render(&imgcatr::Options { palette_preview: false, ..opts.clone() }, img, out);
+        }
+    };
+
+    let formats: Vec<_> = [imgcatr::AnsiOutputFormat::Truecolor,
+                           imgcatr::AnsiOutputFormat::Quadrant,
+                           imgcatr::AnsiOutputFormat::Sixel,
+                           imgcatr::AnsiOutputFormat::Kitty,
+                           imgcatr::AnsiOutputFormat::Ansi256,
+                           imgcatr::AnsiOutputFormat::SimpleBlack,
+                           imgcatr::AnsiOutputFormat::SimpleWhite,
+                           imgcatr::AnsiOutputFormat::Braille,
+                           imgcatr::AnsiOutputFormat::ASCII]
+        .into_iter()
+        .filter(|f| f.colour_count() <= ansi.colour_count())
+        .filter(|&f| {
+            f == ansi ||
+            match f {
+                imgcatr::AnsiOutputFormat::Sixel => opts.caps.sixel,
+                imgcatr::AnsiOutputFormat::Kitty => opts.caps.kitty,
+                _ => true,
+            }
+        })
+        .collect();
+    let preview_size = (opts.size.0, (opts.size.1 / formats.len() as u32).saturating_sub(1).max(1));
+
+    for format in formats {
+        writeln!(out, "--ansi {}", format.name()).unwrap();
+        render(&imgcatr::Options {
+                   size: preview_size,
+                   ansi_out: Some(format),
+                   palette_preview: false,
+                   ..opts.clone()
+               },
+               img,
+               out);
+    }
+}
+
+fn report_accuracy(img: &image::DynamicImage, ansi: Option<imgcatr::AnsiOutputFormat>, opts: &imgcatr::Options) {
+    match ansi {
+        Some(imgcatr::AnsiOutputFormat::Truecolor) | Some(imgcatr::AnsiOutputFormat::Kitty) => eprintln!("Mean colour error: {:.2}", 0.0),
+        _ => {
+            match ansi.and_then(|ansi| palette_colours(ansi, opts)) {
+                Some((colours, lower_colours)) => eprintln!("Mean colour error: {:.2}", imgcatr::ops::palette_error(img, colours, lower_colours)),
+                None => eprintln!("Colour error isn't measured for this output mode"),
+            }
+        }
+    }
+}
+
+/// Colours a palette-based format approximates pixels to.
+type Colours<'a> = &'a [image::Rgb<u8>];
+
+/// Get the colours the upper and lower pixels are approximated to in the specified format, if it's palette-based.
+fn palette_colours(ansi: imgcatr::AnsiOutputFormat, opts: &imgcatr::Options) -> Option<(Colours<'_>, Colours<'_>)> {
+    let colours = match ansi {
+        imgcatr::AnsiOutputFormat::Ansi256 => {
+            let colours = &imgcatr::util::ANSI_COLOURS_256[imgcatr::util::ANSI_256_FIXED_START..];
+            return Some((colours, colours));
+        }
+        imgcatr::AnsiOutputFormat::SimpleWhite | imgcatr::AnsiOutputFormat::SimpleBlack => simple_colours(ansi, opts),
+        _ => return None,
+    };
+    Some((colours, if opts.wide_bg { colours } else { imgcatr::util::bg_colours_for(colours) }))
+}
+
+/// Get the 16 colours the specified simple format approximates pixels to, those from `--palette` if given.
+fn simple_colours(ansi: imgcatr::AnsiOutputFormat, opts: &imgcatr::Options) -> Colours<'_> {
+    match (&opts.palette, ansi) {
+        (Some(palette), _) => palette,
+        (None, imgcatr::AnsiOutputFormat::SimpleWhite) => &imgcatr::util::ANSI_COLOURS_WHITE_BG,
+        (None, _) => &imgcatr::util::ANSI_COLOURS_BLACK_BG,
+    }
+}
+
+/// Write the image like `write_ansi_image()`, left-padded by `offset.0` columns and top-padded by `offset.1` lines.
+fn write_offset_ansi_image<W: Write>(out: &mut W, img: &image::DynamicImage, ansi: imgcatr::AnsiOutputFormat, opts: &imgcatr::Options, offset: (u32, u32)) {
+    for _ in 0..offset.1 {
+        writeln!(out).unwrap();
+    }
+    if offset.0 == 0 {
+        write_ansi_image(out, img, ansi, opts);
+    } else {
+        let mut rendered = Vec::new();
+        write_ansi_image(&mut rendered, img, ansi, opts);
+        imgcatr::ops::write_padded(out, &rendered, offset.0);
+    }
+}
+
+fn write_ansi_image<W: Write>(out: &mut W, img: &image::DynamicImage, ansi: imgcatr::AnsiOutputFormat, opts: &imgcatr::Options) {
+    match ansi {
+        imgcatr::AnsiOutputFormat::Auto => write_ansi_image(out, img, ansi.resolve(&opts.caps, opts.theme), opts),
+        imgcatr::AnsiOutputFormat::Truecolor if opts.bg_only => imgcatr::ops::write_ansi_truecolor_bg_only(out, img),
+        imgcatr::AnsiOutputFormat::Truecolor => imgcatr::ops::write_ansi_truecolor(out, img),
+        imgcatr::AnsiOutputFormat::Quadrant => imgcatr::ops::write_ansi_quadrant(out, img),
+        imgcatr::AnsiOutputFormat::Sixel => imgcatr::ops::write_sixel(out, img),
+        imgcatr::AnsiOutputFormat::Kitty => imgcatr::ops::write_kitty(out, img),
+        imgcatr::AnsiOutputFormat::Ansi256 => imgcatr::ops::write_ansi_256(out, img),
+        imgcatr::AnsiOutputFormat::SimpleWhite | imgcatr::AnsiOutputFormat::SimpleBlack if opts.interpolate_palette => {
+            imgcatr::ops::write_ansi_interpolated(out, img, simple_colours(ansi, opts), opts.wide_bg)
+        }
+        imgcatr::AnsiOutputFormat::SimpleWhite | imgcatr::AnsiOutputFormat::SimpleBlack if opts.wide_bg => {
+            imgcatr::ops::write_ansi_wide_bg(out, img, simple_colours(ansi, opts))
+        }
+        imgcatr::AnsiOutputFormat::SimpleWhite | imgcatr::AnsiOutputFormat::SimpleBlack => imgcatr::ops::write_ansi(out, img, simple_colours(ansi, opts)),
+        imgcatr::AnsiOutputFormat::Braille => imgcatr::ops::write_braille(out, img, opts.threshold, opts.theme.invert_ascii() != opts.ascii_invert),
+        imgcatr::AnsiOutputFormat::ASCII => imgcatr::ops::write_ascii(out, img, &opts.ascii_ramp, opts.theme.invert_ascii() != opts.ascii_invert, opts.linear_luma),
+    }
 }
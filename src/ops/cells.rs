@@ -0,0 +1,84 @@
+use self::super::super::util::{ANSI_COLOURS_BLACK_BG, ANSI_COLOURS_WHITE_BG, ANSI_COLOURS_256, ANSI_256_FIXED_START, bg_colours_for};
+use image::{GenericImageView, DynamicImage, Pixel, Rgb};
+use self::super::super::AnsiOutputFormat;
+use self::super::create_colourtable;
+
+
+/// A single rendered terminal cell, holding two vertically-stacked pixels
+///
+/// Cells in the last line of an odd-height image only hold an upper pixel, which `lower` repeats.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct Cell {
+    /// The colour of the upper pixel, as displayed.
+    pub upper: Rgb<u8>,
+    /// The colour of the lower pixel, as displayed.
+    pub lower: Rgb<u8>,
+    /// Indices of the upper and lower colours into the palette's foreground and background colours,
+    /// or into `ANSI_COLOURS_256` for the 256-colour format, or `None` for formats that aren't palette-based.
+    pub palette: Option<(usize, usize)>,
+}
+
+/// Render the specified image into a line-major table of cells in the specified format, for displaying it yourself.
+///
+/// The simple and 256-colour formats approximate the pixels like `create_colourtable()` and `write_ansi_256()`; truecolor, quadrant blocks, sixel, Kitty, braille, and ASCII art use them as-is,
+/// as does `Auto`, so `AnsiOutputFormat::resolve()` it first.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{DynamicImage, Rgb, RgbImage};
+/// # use imgcatr::ops::{Cell, render_cells};
+/// # use imgcatr::AnsiOutputFormat;
+/// # fn main() {
+/// let mut img = RgbImage::from_pixel(2, 2, Rgb([0xC0, 0x10, 0x10]));
+/// img.put_pixel(0, 1, Rgb([0x00, 0x00, 0x00]));
+/// let img = DynamicImage::ImageRgb8(img);
+///
+/// let cells = render_cells(&img, AnsiOutputFormat::Truecolor);
+/// assert_eq!(cells.len(), 1);
+/// assert_eq!(cells[0][0], Cell { upper: Rgb([0xC0, 0x10, 0x10]), lower: Rgb([0x00, 0x00, 0x00]), palette: None });
+///
+/// let cells = render_cells(&img, AnsiOutputFormat::SimpleBlack);
+/// assert_eq!(cells[0][0], Cell { upper: Rgb([0xCD, 0x00, 0x00]), lower: Rgb([0x00, 0x00, 0x00]), palette: Some((1, 0)) });
+/// # }
+/// ```
+pub fn render_cells(img: &DynamicImage, format: AnsiOutputFormat) -> Vec<Vec<Cell>> {
+    let (colours, bg_colours, offset): (&[Rgb<u8>], &[Rgb<u8>], usize) = match format {
+        AnsiOutputFormat::SimpleBlack => (&ANSI_COLOURS_BLACK_BG, bg_colours_for(&ANSI_COLOURS_BLACK_BG), 0),
+        AnsiOutputFormat::SimpleWhite => (&ANSI_COLOURS_WHITE_BG, bg_colours_for(&ANSI_COLOURS_WHITE_BG), 0),
+        AnsiOutputFormat::Ansi256 => (&ANSI_COLOURS_256[ANSI_256_FIXED_START..], &ANSI_COLOURS_256[ANSI_256_FIXED_START..], ANSI_256_FIXED_START),
+        AnsiOutputFormat::Auto | AnsiOutputFormat::Truecolor | AnsiOutputFormat::Quadrant | AnsiOutputFormat::Sixel | AnsiOutputFormat::Kitty | AnsiOutputFormat::Braille | AnsiOutputFormat::ASCII => {
+            let (width, height) = img.dimensions();
+            return (0..height.div_ceil(2))
+                .map(|y| {
+                    (0..width)
+                        .map(|x| {
+                            Cell {
+                                upper: img.get_pixel(x, y * 2).to_rgb(),
+                                lower: img.get_pixel(x, (y * 2 + 1).min(height - 1)).to_rgb(),
+                                palette: None,
+                            }
+                        })
+                        .collect()
+                })
+                .collect();
+        }
+    };
+
+    create_colourtable(img, colours, bg_colours)
+        .into_iter()
+        .map(|line| {
+            line.into_iter()
+                .map(|(upper_clr, lower_clr)| {
+                    Cell {
+                        upper: colours[upper_clr],
+                        lower: bg_colours[lower_clr],
+                        palette: Some((offset + upper_clr, offset + lower_clr)),
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
@@ -0,0 +1,116 @@
+use image::{GenericImageView, DynamicImage, GrayImage, Luma, Pixel};
+use image::imageops::FilterType;
+use self::super::super::util::luminance;
+
+
+/// Radius of the square window SSIM statistics are gathered over, making for 7x7 windows.
+static SSIM_WINDOW_RADIUS: u32 = 3;
+
+/// Stabilising constants for SSIM's divisions, `(0.01 * 255)²` and `(0.03 * 255)²`.
+static SSIM_C1: f64 = 6.5025;
+static SSIM_C2: f64 = 58.5225;
+
+
+/// Metric to compare images with
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CompareMetric {
+    /// Mean squared error of the colour channels, `0` for identical images
+    Mse,
+    /// Mean structural similarity of the luminance, `1` for identical images
+    Ssim,
+}
+
+/// Compare two images, returning the overall score in the specified metric and a map of where they differ,
+/// brighter where they differ more.
+///
+/// The second image is resized to the first one's size if they differ.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{DynamicImage, Luma, Rgb, RgbImage};
+/// # use imgcatr::ops::{CompareMetric, compare_images};
+/// # fn main() {
+/// let grey = DynamicImage::ImageRgb8(RgbImage::from_pixel(8, 8, Rgb([0x80, 0x80, 0x80])));
+/// let mut spotted = grey.to_rgb8();
+/// spotted.put_pixel(4, 4, Rgb([0xFF, 0xFF, 0xFF]));
+/// let spotted = DynamicImage::ImageRgb8(spotted);
+///
+/// let (score, map) = compare_images(&grey, &grey, CompareMetric::Mse);
+/// assert_eq!(score, 0.0);
+/// assert!(map.pixels().all(|px| px == &Luma([0])));
+///
+/// let (score, map) = compare_images(&grey, &spotted, CompareMetric::Mse);
+/// assert_eq!(score, 127.0 * 127.0 / 64.0);
+/// assert_eq!(map.get_pixel(4, 4), &Luma([127]));
+///
+/// assert_eq!(compare_images(&grey, &grey, CompareMetric::Ssim).0, 1.0);
+/// let (score, map) = compare_images(&grey, &spotted, CompareMetric::Ssim);
+/// assert!(score < 1.0);
+/// assert!(map.get_pixel(4, 4)[0] > map.get_pixel(0, 0)[0]);
+/// # }
+/// ```
+pub fn compare_images(lhs: &DynamicImage, rhs: &DynamicImage, metric: CompareMetric) -> (f64, GrayImage) {
+    let resized;
+    let rhs = if rhs.dimensions() != lhs.dimensions() {
+        resized = rhs.resize_exact(lhs.width(), lhs.height(), FilterType::Triangle);
+        &resized
+    } else {
+        rhs
+    };
+
+    match metric {
+        CompareMetric::Mse => mse(lhs, rhs),
+        CompareMetric::Ssim => ssim(lhs, rhs),
+    }
+}
+
+fn mse(lhs: &DynamicImage, rhs: &DynamicImage) -> (f64, GrayImage) {
+    let (width, height) = lhs.dimensions();
+    let mut map = GrayImage::new(width, height);
+    let mut total = 0.0;
+
+    for (x, y, lhs_px) in lhs.pixels() {
+        let (lhs_px, rhs_px) = (lhs_px.to_rgb(), rhs.get_pixel(x, y).to_rgb());
+        let error = (0..3).map(|c| (lhs_px[c] as f64 - rhs_px[c] as f64).powi(2)).sum::<f64>() / 3.0;
+        total += error;
+        map.put_pixel(x, y, Luma([error.sqrt().round() as u8]));
+    }
+
+    (if width * height == 0 { 0.0 } else { total / (width * height) as f64 }, map)
+}
+
+fn ssim(lhs: &DynamicImage, rhs: &DynamicImage) -> (f64, GrayImage) {
+    let (width, height) = lhs.dimensions();
+    let luma = |img: &DynamicImage| img.pixels().map(|(_, _, px)| luminance(px.to_rgb()) as f64).collect::<Vec<_>>();
+    let (lhs, rhs) = (luma(lhs), luma(rhs));
+    let mut map = GrayImage::new(width, height);
+    let mut total = 0.0;
+
+    for y in 0..height {
+        for x in 0..width {
+            let (x_range, y_range) = (x.saturating_sub(SSIM_WINDOW_RADIUS)..(x + SSIM_WINDOW_RADIUS + 1).min(width),
+                                      y.saturating_sub(SSIM_WINDOW_RADIUS)..(y + SSIM_WINDOW_RADIUS + 1).min(height));
+            let window = || y_range.clone().flat_map(|wy| x_range.clone().map(move |wx| (wy * width + wx) as usize));
+            let count = window().count() as f64;
+
+            let (lhs_mean, rhs_mean) = (window().map(|i| lhs[i]).sum::<f64>() / count, window().map(|i| rhs[i]).sum::<f64>() / count);
+            let (mut lhs_var, mut rhs_var, mut covar) = (0.0, 0.0, 0.0);
+            for i in window() {
+                lhs_var += (lhs[i] - lhs_mean).powi(2);
+                rhs_var += (rhs[i] - rhs_mean).powi(2);
+                covar += (lhs[i] - lhs_mean) * (rhs[i] - rhs_mean);
+            }
+            let (lhs_var, rhs_var, covar) = (lhs_var / count, rhs_var / count, covar / count);
+
+            let similarity = ((2.0 * lhs_mean * rhs_mean + SSIM_C1) * (2.0 * covar + SSIM_C2)) /
+                             ((lhs_mean.powi(2) + rhs_mean.powi(2) + SSIM_C1) * (lhs_var + rhs_var + SSIM_C2));
+            total += similarity;
+            map.put_pixel(x, y, Luma([((1.0 - similarity).clamp(0.0, 1.0) * 255.0).round() as u8]));
+        }
+    }
+
+    (if width * height == 0 { 1.0 } else { total / (width * height) as f64 }, map)
+}
@@ -0,0 +1,94 @@
+use self::super::super::util::{ANSI_RESET_ATTRIBUTES, ANSI_SHOW_CURSOR};
+use std::io::{Write, stdout};
+use std::sync::Once;
+
+#[cfg(unix)]
+use libc::{c_int, c_void};
+
+
+/// `ANSI_SHOW_CURSOR` followed by `ANSI_RESET_ATTRIBUTES`, for use where formatting isn't allowed.
+#[cfg(any(unix, target_os = "windows"))]
+static RESET_SEQUENCE: &[u8] = b"\x1B[?25h\x1B[0m";
+
+static INSTALL_HANDLERS: Once = Once::new();
+
+
+/// Guard restoring the terminal state when dropped, even when unwinding from a panic,
+/// as well as when the process is interrupted by a signal (or a console control event, on Windows) while it's alive.
+///
+/// The cursor is shown and all ANSI attributes are reset.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use imgcatr::ops::ResetGuard;
+/// # use imgcatr::util::ANSI_HIDE_CURSOR;
+/// let _guard = ResetGuard::new();
+/// print!("{}", ANSI_HIDE_CURSOR);
+/// // Draw for a while, possibly interrupted…
+/// ```
+#[derive(Debug)]
+pub struct ResetGuard {
+    _priv: (),
+}
+
+impl ResetGuard {
+    /// Start guarding the terminal state, installing the interruption handlers if that hasn't happened yet.
+    pub fn new() -> ResetGuard {
+        INSTALL_HANDLERS.call_once(install_handlers);
+        ResetGuard { _priv: () }
+    }
+}
+
+impl Default for ResetGuard {
+    fn default() -> ResetGuard {
+        ResetGuard::new()
+    }
+}
+
+impl Drop for ResetGuard {
+    fn drop(&mut self) {
+        let mut out = stdout();
+        let _ = write!(out, "{}{}", ANSI_SHOW_CURSOR, ANSI_RESET_ATTRIBUTES);
+        let _ = out.flush();
+    }
+}
+
+
+#[cfg(unix)]
+fn install_handlers() {
+    for &sig in &[libc::SIGINT, libc::SIGTERM, libc::SIGHUP] {
+        unsafe { libc::signal(sig, handle_signal as *const () as libc::sighandler_t) };
+    }
+}
+
+/// Write the reset sequence straight to the file descriptor, which is async-signal-safe, then die as if unhandled.
+#[cfg(unix)]
+extern "C" fn handle_signal(sig: c_int) {
+    unsafe {
+        libc::write(libc::STDOUT_FILENO, RESET_SEQUENCE.as_ptr() as *const c_void, RESET_SEQUENCE.len());
+        libc::signal(sig, libc::SIG_DFL);
+        libc::raise(sig);
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn install_handlers() {
+    unsafe { winapi::um::consoleapi::SetConsoleCtrlHandler(Some(handle_ctrl), 1) };
+}
+
+/// Write the reset sequence straight to the console handle, since the main thread may be holding `stdout()` locked,
+/// then let the default handler exit.
+#[cfg(target_os = "windows")]
+unsafe extern "system" fn handle_ctrl(_: winapi::shared::minwindef::DWORD) -> winapi::shared::minwindef::BOOL {
+    let console_h = winapi::um::processenv::GetStdHandle(winapi::um::winbase::STD_OUTPUT_HANDLE);
+    winapi::um::fileapi::WriteFile(console_h,
+                                   RESET_SEQUENCE.as_ptr() as *const _,
+                                   RESET_SEQUENCE.len() as u32,
+                                   &mut 0,
+                                   std::ptr::null_mut());
+    0
+}
+
+#[cfg(not(any(unix, target_os = "windows")))]
+fn install_handlers() {}
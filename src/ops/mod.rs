@@ -1,45 +1,81 @@
 //! Main functions doing actual work.
 //!
-//! Use `guess_format()` to get the image format from a path,
+//! Use `guess_format()` to get the image format from a path (or `guess_format_from_memory()` from a buffer),
 //! then read the image using `load_image()` to the size given by `image_resized_size()`,
-//! resize it to terminal size with `resize_image()`
-//! and display it with `write_[no_]ansi[_truecolor]()`,
-//! or display it yourself with approximations from `create_colourtable()`.
+//! resize it to terminal size with `resize_image()` (or do both with `prepare_image()`),
+//! and display it with `write_[no_]ansi[_truecolor]()` (or `write_sixel()`/`write_kitty()`, sized with `pixel_resized_size()`),
+//! or display it yourself with approximations from `create_colourtable()` or cells from `render_cells()`.
 
 
-use self::super::util::{ANSI_BG_COLOUR_ESCAPES, ANSI_RESET_ATTRIBUTES, ANSI_COLOUR_ESCAPES, JPEG_MAGIC, BMP_MAGIC, ICO_MAGIC, GIF_MAGIC, PNG_MAGIC,
-                        closest_colour, bg_colours_for};
-use image::{self, GenericImageView, DynamicImage, ImageFormat, Pixel};
-use std::io::{BufReader, Write, Read};
+use self::super::util::{ANSI_BG_COLOUR_ESCAPES, ANSI_BG_COLOUR_ESCAPES_16, LOWER_EIGHTH_BLOCKS, QUADRANT_BLOCKS, BRAILLE_DOTS, ANSI_RESET_ATTRIBUTES, ANSI_COLOUR_ESCAPES, JPEG_MAGIC, BMP_MAGIC, ICO_MAGIC, GIF_MAGIC, PNG_MAGIC,
+                        WEBP_MAGIC, WEBP_FORM_MAGIC, TIFF_MAGIC_LE, TIFF_MAGIC_BE, HDR_MAGIC, EXR_MAGIC, QOI_MAGIC, AVIF_FTYP_MAGIC, AVIF_BRANDS,
+                        PDF_MAGIC, TGA_FOOTER_MAGIC, EXIF_MAGIC, OSC52_MAX_PAYLOAD, KITTY_CHUNK_SIZE, ANSI_COLOURS_256, ANSI_256_FIXED_START, ColourLut,
+                        closest_colour, colour_distance, build_colour_lut, bg_colours_for, blend, srgb_encode, blend_over, luminance, luminance_linear, base64_encode, mul_str};
+use image::{self, ImageDecoder, GenericImageView, DynamicImage, ImageFormat, ImageResult, Rgb32FImage, ColorType, Pixel, Frame, Delay, Rgb, Rgba, AnimationDecoder};
+use image::codecs::gif::{GifDecoder, GifEncoder, Repeat};
+use image::codecs::hdr::HdrDecoder;
+use image::codecs::jpeg::JpegDecoder;
+use image::codecs::webp::WebPDecoder;
+use std::io::{BufRead, BufReader, Cursor, Write, Read, Seek, SeekFrom};
 use image::imageops::FilterType;
-use self::super::Error;
-use std::path::PathBuf;
+use self::super::{Anchor, CropRect, Error, ResizeMode, ToneMapOperator};
+use std::time::Duration;
+use std::mem;
+use std::path::{Path, PathBuf};
 use std::ops::Index;
 use std::fs::File;
+use std::thread;
 
+mod pdf;
+mod cells;
+mod compare;
+mod guard;
+mod resize;
+mod throttle;
 mod no_ansi;
+mod sixel;
+mod query;
+mod remote;
 
+pub use self::pdf::load_pdf_page;
+pub use self::remote::{fetch_image, is_url};
+pub use self::cells::{Cell, render_cells};
+pub use self::compare::{CompareMetric, compare_images};
+pub use self::guard::ResetGuard;
+pub use self::resize::ResizeWatcher;
+pub use self::throttle::FrameLimiter;
 pub use self::no_ansi::write_no_ansi;
+pub use self::sixel::write_sixel;
+pub use self::query::query_terminal_background;
+pub(crate) use self::query::query_terminal;
 
 
 /// Guess the image format from its extension or magic.
 ///
+/// TGA files are recognised by the footer TGA 2.0 added, so older ones need the `.tga` extension.
+///
 /// # Examples
 ///
 /// Correct:
 ///
 /// ```
 /// # extern crate image;
-/// # extern crate termimage;
+/// # extern crate imgcatr;
 /// # use image::ImageFormat;
 /// # use std::path::PathBuf;
-/// # use termimage::ops::guess_format;
+/// # use imgcatr::ops::guess_format;
 /// # fn main() {
 /// assert_eq!(guess_format(&(String::new(), PathBuf::from("img.png"))), Ok(ImageFormat::Png));
 /// assert_eq!(guess_format(&(String::new(), PathBuf::from("img.jpg"))), Ok(ImageFormat::Jpeg));
 /// assert_eq!(guess_format(&(String::new(), PathBuf::from("img.gif"))), Ok(ImageFormat::Gif));
 /// assert_eq!(guess_format(&(String::new(), PathBuf::from("img.bmp"))), Ok(ImageFormat::Bmp));
 /// assert_eq!(guess_format(&(String::new(), PathBuf::from("img.ico"))), Ok(ImageFormat::Ico));
+/// assert_eq!(guess_format(&(String::new(), PathBuf::from("img.qoi"))), Ok(ImageFormat::Qoi));
+/// assert_eq!(guess_format(&(String::new(), PathBuf::from("img.pgm"))), Ok(ImageFormat::Pnm));
+/// assert_eq!(guess_format(&(String::new(), PathBuf::from("img.pbm"))), Ok(ImageFormat::Pnm));
+/// if cfg!(feature = "avif") {
+///     assert_eq!(guess_format(&(String::new(), PathBuf::from("img.avif"))), Ok(ImageFormat::Avif));
+/// }
 /// # }
 /// ```
 ///
@@ -47,191 +83,2319 @@ pub use self::no_ansi::write_no_ansi;
 ///
 /// ```
 /// # use std::path::PathBuf;
-/// # use termimage::Error;
-/// # use termimage::ops::guess_format;
+/// # use imgcatr::Error;
+/// # use imgcatr::ops::guess_format;
 /// assert_eq!(guess_format(&("src/ops.rs".to_string(), PathBuf::from("src/ops/mod.rs"))),
 /// Err(Error::GuessingFormatFailed("src/ops.rs".to_string())));
 /// ```
 pub fn guess_format(file: &(String, PathBuf)) -> Result<ImageFormat, Error> {
     file.1
         .extension()
-        .and_then(|ext| match &ext.to_str().unwrap().to_lowercase()[..] {
+        .and_then(|ext| match &ext.to_string_lossy().to_lowercase()[..] {
             "png" => Some(Ok(ImageFormat::Png)),
             "jpg" | "jpeg" | "jpe" | "jif" | "jfif" | "jfi" => Some(Ok(ImageFormat::Jpeg)),
             "gif" => Some(Ok(ImageFormat::Gif)),
             "webp" => Some(Ok(ImageFormat::WebP)),
-            "ppm" => Some(Ok(ImageFormat::Pnm)),
+            "pbm" | "pgm" | "ppm" | "pnm" => Some(Ok(ImageFormat::Pnm)),
             "tiff" | "tif" => Some(Ok(ImageFormat::Tiff)),
             "tga" => Some(Ok(ImageFormat::Tga)),
             "bmp" | "dib" => Some(Ok(ImageFormat::Bmp)),
             "ico" => Some(Ok(ImageFormat::Ico)),
             "hdr" => Some(Ok(ImageFormat::Hdr)),
+            "exr" => Some(Ok(ImageFormat::OpenExr)),
+            "qoi" => Some(Ok(ImageFormat::Qoi)),
+            #[cfg(feature = "avif")]
+            "avif" => Some(Ok(ImageFormat::Avif)),
             _ => None,
         })
-        .unwrap_or_else(|| {
-            let mut buf = [0; 32];
-            let read = File::open(&file.1).map_err(|_| Error::OpeningImageFailed(file.0.clone()))?.read(&mut buf).unwrap();
-            let buf = &buf[..read];
-
-            if buf.len() >= PNG_MAGIC.len() && &buf[..PNG_MAGIC.len()] == PNG_MAGIC {
-                Ok(ImageFormat::Png)
-            } else if buf.len() >= JPEG_MAGIC.len() && &buf[..JPEG_MAGIC.len()] == JPEG_MAGIC {
-                Ok(ImageFormat::Jpeg)
-            } else if buf.len() >= GIF_MAGIC.len() && &buf[..GIF_MAGIC.len()] == GIF_MAGIC {
-                Ok(ImageFormat::Gif)
-            } else if buf.len() >= BMP_MAGIC.len() && &buf[..BMP_MAGIC.len()] == BMP_MAGIC {
-                Ok(ImageFormat::Bmp)
-            } else if buf.len() >= ICO_MAGIC.len() && &buf[..ICO_MAGIC.len()] == ICO_MAGIC {
-                Ok(ImageFormat::Ico)
-            } else {
-                Err(Error::GuessingFormatFailed(file.0.clone()))
-            }
-        })
+        .unwrap_or_else(|| sniff_format(file)?.ok_or_else(|| Error::GuessingFormatFailed(file.0.clone())))
 }
 
-/// Load an image from the specified file as the specified format.
+/// Guess the image format of the specified in-memory buffer from its magic number or footer, like `guess_format()`
+/// does for files without a known extension.
 ///
-/// Get the image fromat with `guess_format()`.
-pub fn load_image(file: &(String, PathBuf), format: ImageFormat) -> Result<DynamicImage, Error> {
-    Ok(image::load(BufReader::new(File::open(&file.1).map_err(|_| Error::OpeningImageFailed(file.0.clone()))?),
-                   format)
-        .unwrap())
+/// The name is only used in errors.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::ImageFormat;
+/// # use imgcatr::ops::guess_format_from_memory;
+/// # use imgcatr::Error;
+/// # use std::fs;
+/// # fn main() {
+/// assert_eq!(guess_format_from_memory(&fs::read("assets/rust.png").unwrap(), "<stdin>"), Ok(ImageFormat::Png));
+/// assert_eq!(guess_format_from_memory(b"GIF89a", "<stdin>"), Ok(ImageFormat::Gif));
+/// assert_eq!(guess_format_from_memory(b"RIFF\x24\0\0\0WEBPVP8 ", "<stdin>"), Ok(ImageFormat::WebP));
+/// assert_eq!(guess_format_from_memory(b"RIFF\x24\0\0\0WAVEfmt ", "<stdin>"), Err(Error::GuessingFormatFailed("<stdin>".to_string())));
+/// assert_eq!(guess_format_from_memory(b"II*\0\x08\0\0\0", "<stdin>"), Ok(ImageFormat::Tiff));
+/// assert_eq!(guess_format_from_memory(b"MM\0*\0\0\0\x08", "<stdin>"), Ok(ImageFormat::Tiff));
+/// assert_eq!(guess_format_from_memory(b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n", "<stdin>"), Ok(ImageFormat::Hdr));
+/// assert_eq!(guess_format_from_memory(b"qoif\0\0\0\x04\0\0\0\x02\x04\0", "<stdin>"), Ok(ImageFormat::Qoi));
+/// assert_eq!(guess_format_from_memory(b"\0\0\0\x1Cftypavif\0\0\0\0", "<stdin>").is_ok(), cfg!(feature = "avif"));
+/// assert_eq!(guess_format_from_memory(b"P5\n2 2\n255\n\0\0\0\0", "<stdin>"), Ok(ImageFormat::Pnm));
+/// assert_eq!(guess_format_from_memory(b"P1 1 1 0", "<stdin>"), Ok(ImageFormat::Pnm));
+/// assert_eq!(guess_format_from_memory(b"P7\nWIDTH 1\n", "<stdin>"), Err(Error::GuessingFormatFailed("<stdin>".to_string())));
+/// assert_eq!(guess_format_from_memory(b"not an image", "<stdin>"), Err(Error::GuessingFormatFailed("<stdin>".to_string())));
+/// # }
+/// ```
+pub fn guess_format_from_memory(buf: &[u8], name: &str) -> Result<ImageFormat, Error> {
+    magic_format(buf).or_else(|| footer_format(buf)).ok_or_else(|| Error::GuessingFormatFailed(name.to_string()))
 }
 
-/// Get the image size to downscale to, given its size, the terminal's size and whether to preserve its aspect.
+/// Check whether the specified file is a JPEG with four colour components, that is CMYK or YCCK.
+///
+/// Those are converted to RGB when loaded, but without their colour profiles, so their colours may be off.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{GenericImageView, ImageFormat, Rgba};
+/// # use imgcatr::ops::{is_cmyk_jpeg, load_image};
+/// # use std::path::PathBuf;
+/// # fn main() {
+/// let file = ("assets/cmyk.jpg".to_string(), PathBuf::from("assets/cmyk.jpg"));
+/// assert_eq!(is_cmyk_jpeg(&file), Ok(true));
+/// assert_eq!(is_cmyk_jpeg(&("rust.png".to_string(), PathBuf::from("assets/rust.png"))), Ok(false));
 ///
-/// The resulting image size is twice as tall as the terminal size because we print two pixels per cell (height-wise).
-pub fn image_resized_size(size: (u32, u32), term_size: (u32, u32), preserve_aspect: bool) -> (u32, u32) {
-    if !preserve_aspect {
-        return (term_size.0, term_size.1 * 2);
+/// // Red on the left and blue on the right, as encoded
+/// let img = load_image(&file, ImageFormat::Jpeg).unwrap();
+/// assert_eq!(img.get_pixel(2, 4), Rgba([0xFF, 0x00, 0x00, 0xFF]));
+/// assert_eq!(img.get_pixel(13, 4), Rgba([0x00, 0x00, 0xFF, 0xFF]));
+/// # }
+/// ```
+pub fn is_cmyk_jpeg(file: &(String, PathBuf)) -> Result<bool, Error> {
+    let f = File::open(&file.1).map_err(|_| Error::OpeningImageFailed(file.0.clone()))?;
+    Ok(jpeg_components(BufReader::new(f)).ok().flatten() == Some(4))
+}
+
+/// Get the amount of colour components from the frame header of the JPEG in the specified reader, if it is one.
+fn jpeg_components<R: Read + Seek>(reader: R) -> std::io::Result<Option<u8>> {
+    find_jpeg_segment(reader, |marker, _, reader| {
+        if matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+            // Precision, height, and width come before the component count
+            let mut header = [0; 6];
+            reader.read_exact(&mut header)?;
+            Ok(Some(header[5]))
+        } else {
+            Ok(None)
+        }
+    })
+}
+
+/// Walk the segments of the JPEG in the specified reader up to its scan data, if it is one,
+/// until the specified function finds something in one, given its marker and the length of its payload, which it's positioned at.
+fn find_jpeg_segment<R, T, F>(mut reader: R, mut visit: F) -> std::io::Result<Option<T>>
+    where R: Read + Seek,
+          F: FnMut(u8, u64, &mut R) -> std::io::Result<Option<T>>
+{
+    let mut buf = [0; 2];
+    reader.read_exact(&mut buf)?;
+    if buf != [0xFF, 0xD8] {
+        return Ok(None);
     }
 
-    let nwidth = term_size.0;
-    let nheight = term_size.1 * 2;
-    let (width, height) = size;
+    loop {
+        reader.read_exact(&mut buf[..1])?;
+        if buf[0] != 0xFF {
+            return Ok(None);
+        }
+        let mut marker = 0xFF;
+        while marker == 0xFF {
+            reader.read_exact(&mut buf[..1])?;
+            marker = buf[0];
+        }
 
-    let ratio = width as f32 / height as f32;
-    let nratio = nwidth as f32 / nheight as f32;
+        match marker {
+            // Start of scan or end of image, so no more headers
+            0xDA | 0xD9 => return Ok(None),
+            // Markers without a length
+            0x01 | 0xD0..=0xD7 => continue,
+            _ => {}
+        }
 
-    let scale = if nratio > ratio {
-        nheight as f32 / height as f32
-    } else {
-        nwidth as f32 / width as f32
+        reader.read_exact(&mut buf)?;
+        let len = (u16::from_be_bytes(buf) as u64).saturating_sub(2);
+        let payload = reader.stream_position()?;
+        if let Some(found) = visit(marker, len, &mut reader)? {
+            return Ok(Some(found));
+        }
+        reader.seek(SeekFrom::Start(payload + len))?;
+    }
+}
+
+/// Get the EXIF orientation of the JPEG or TIFF image in the specified reader, if it has one.
+fn exif_orientation<R: Read + Seek>(reader: R, format: ImageFormat) -> std::io::Result<Option<u8>> {
+    match format {
+        ImageFormat::Jpeg => {
+            find_jpeg_segment(reader, |marker, len, reader| {
+                if marker != 0xE1 {
+                    return Ok(None);
+                }
+                let mut payload = vec![0; len as usize];
+                reader.read_exact(&mut payload)?;
+                Ok(payload.strip_prefix(EXIF_MAGIC).and_then(|tiff| tiff_orientation(Cursor::new(tiff)).ok().flatten()))
+            })
+        }
+        ImageFormat::Tiff => tiff_orientation(reader),
+        _ => Ok(None),
+    }
+}
+
+/// Get the orientation tag from the first image file directory of the TIFF structure in the specified reader, if it has a valid one.
+fn tiff_orientation<R: Read + Seek>(mut reader: R) -> std::io::Result<Option<u8>> {
+    let base = reader.stream_position()?;
+    let mut header = [0; 8];
+    reader.read_exact(&mut header)?;
+    let big_endian = match &header[..4] {
+        m if m == TIFF_MAGIC_LE => false,
+        m if m == TIFF_MAGIC_BE => true,
+        _ => return Ok(None),
     };
+    let u16_of = |b: [u8; 2]| if big_endian { u16::from_be_bytes(b) } else { u16::from_le_bytes(b) };
+    let u32_of = |b: [u8; 4]| if big_endian { u32::from_be_bytes(b) } else { u32::from_le_bytes(b) };
 
-    ((width as f32 * scale) as u32, (height as f32 * scale) as u32)
+    reader.seek(SeekFrom::Start(base + u32_of([header[4], header[5], header[6], header[7]]) as u64))?;
+    let mut count = [0; 2];
+    reader.read_exact(&mut count)?;
+    for _ in 0..u16_of(count) {
+        // Tag, type, value count, and the value itself if it fits in 4 bytes
+        let mut entry = [0; 12];
+        reader.read_exact(&mut entry)?;
+        if u16_of([entry[0], entry[1]]) == 0x0112 && u16_of([entry[2], entry[3]]) == 3 {
+            let orientation = u16_of([entry[8], entry[9]]);
+            return Ok(Some(orientation as u8).filter(|_| (1..=8).contains(&orientation)));
+        }
+    }
+    Ok(None)
 }
 
-/// Resize the specified image to the specified size.
-pub fn resize_image(img: &DynamicImage, size: (u32, u32)) -> DynamicImage {
-    img.resize_exact(size.0, size.1, FilterType::Nearest)
+/// Transform the specified image as the specified EXIF orientation says it should be displayed.
+///
+/// The EXIF orientation is 1 for images stored as displayed, 2 to 4 for ones flipped horizontally, rotated by 180°,
+/// and flipped vertically, and 5 to 8 for ones transposed, rotated by 90°, transversed, and rotated by 270° clockwise.
+/// Other values leave the image as-is.
+///
+/// This happens when loading JPEGs and TIFFs, so there's rarely a need to call it yourself.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{DynamicImage, GenericImageView, Rgb, RgbImage};
+/// # use imgcatr::ops::apply_orientation;
+/// # fn main() {
+/// let mut img = RgbImage::new(3, 2);
+/// img.put_pixel(0, 0, Rgb([0xFF, 0x00, 0x00]));
+/// let img = DynamicImage::ImageRgb8(img);
+///
+/// let rotated = apply_orientation(img.clone(), 6);
+/// assert_eq!(rotated.dimensions(), (2, 3));
+/// assert_eq!(rotated.get_pixel(1, 0)[0], 0xFF);
+/// assert_eq!(apply_orientation(img.clone(), 1), img);
+/// # }
+/// ```
+pub fn apply_orientation(img: DynamicImage, orientation: u8) -> DynamicImage {
+    match orientation {
+        2 => img.fliph(),
+        3 => img.rotate180(),
+        4 => img.flipv(),
+        5 => img.rotate90().fliph(),
+        6 => img.rotate90(),
+        7 => img.rotate270().fliph(),
+        8 => img.rotate270(),
+        _ => img,
+    }
 }
 
-/// Create a line-major table of (upper, lower) colour approximation indices given the supported colours therefor.
+/// Get the image formats this build can decode, in addition to PDF documents with the `pdf` feature.
 ///
 /// # Examples
 ///
-/// Approximate `img` to ANSI and display it to stdout.
+/// ```
+/// # extern crate imgcatr;
+/// # extern crate image;
+/// # use imgcatr::ops::decodable_formats;
+/// # use image::ImageFormat;
+/// # fn main() {
+/// let formats = decodable_formats();
+/// assert!(formats.contains(&ImageFormat::Png));
+/// assert!(formats.iter().all(|f| f.reading_enabled()));
+/// # }
+/// ```
+pub fn decodable_formats() -> Vec<ImageFormat> {
+    ImageFormat::all().filter(|f| f.reading_enabled()).collect()
+}
+
+/// Check whether the specified file is a PDF document, by its extension or magic, to be loaded with `load_pdf_page()`.
+///
+/// # Examples
+///
+/// ```
+/// # use std::path::PathBuf;
+/// # use imgcatr::ops::is_pdf;
+/// assert_eq!(is_pdf(&(String::new(), PathBuf::from("doc.pdf"))), Ok(true));
+/// assert_eq!(is_pdf(&(String::new(), PathBuf::from("img.png"))), Ok(false));
+/// assert_eq!(is_pdf(&(String::new(), PathBuf::from("src/ops/mod.rs"))), Ok(false));
+/// ```
+pub fn is_pdf(file: &(String, PathBuf)) -> Result<bool, Error> {
+    match file.1.extension().map(|ext| ext.to_string_lossy().to_lowercase()) {
+        Some(ref ext) if ext == "pdf" => Ok(true),
+        Some(_) => Ok(false),
+        None => {
+            let mut buf = [0; 4];
+            let read = File::open(&file.1).map_err(|_| Error::OpeningImageFailed(file.0.clone()))?.read(&mut buf).unwrap();
+            Ok(&buf[..read] == PDF_MAGIC)
+        }
+    }
+}
+
+/// Guess the image format from the magic number at the start of the file, or the footer at the end of it.
+fn sniff_format(file: &(String, PathBuf)) -> Result<Option<ImageFormat>, Error> {
+    let mut f = File::open(&file.1).map_err(|_| Error::OpeningImageFailed(file.0.clone()))?;
+    let mut buf = [0; 32];
+    let read = f.read(&mut buf).unwrap();
+    if let Some(format) = magic_format(&buf[..read]) {
+        return Ok(Some(format));
+    }
+
+    let mut footer = [0; 18];
+    if f.seek(SeekFrom::End(-(footer.len() as i64))).is_err() || f.read_exact(&mut footer).is_err() {
+        return Ok(None);
+    }
+    Ok(footer_format(&footer))
+}
+
+/// Guess the image format from the signature at the end of the buffer.
+fn footer_format(buf: &[u8]) -> Option<ImageFormat> {
+    if buf.ends_with(TGA_FOOTER_MAGIC) {
+        Some(ImageFormat::Tga)
+    } else {
+        None
+    }
+}
+
+/// Guess the image format from the magic number at the start of the buffer.
+fn magic_format(buf: &[u8]) -> Option<ImageFormat> {
+    if magic_at(buf, 0, PNG_MAGIC) {
+        Some(ImageFormat::Png)
+    } else if magic_at(buf, 0, JPEG_MAGIC) {
+        Some(ImageFormat::Jpeg)
+    } else if magic_at(buf, 0, GIF_MAGIC) {
+        Some(ImageFormat::Gif)
+    } else if magic_at(buf, 0, WEBP_MAGIC) && magic_at(buf, 8, WEBP_FORM_MAGIC) {
+        Some(ImageFormat::WebP)
+    } else if magic_at(buf, 0, TIFF_MAGIC_LE) || magic_at(buf, 0, TIFF_MAGIC_BE) {
+        Some(ImageFormat::Tiff)
+    } else if magic_at(buf, 0, HDR_MAGIC) {
+        Some(ImageFormat::Hdr)
+    } else if magic_at(buf, 0, EXR_MAGIC) {
+        Some(ImageFormat::OpenExr)
+    } else if magic_at(buf, 0, QOI_MAGIC) {
+        Some(ImageFormat::Qoi)
+    } else if cfg!(feature = "avif") && magic_at(buf, 4, AVIF_FTYP_MAGIC) && AVIF_BRANDS.iter().any(|brand| magic_at(buf, 8, brand)) {
+        Some(ImageFormat::Avif)
+    } else if is_pnm_magic(buf) {
+        Some(ImageFormat::Pnm)
+    } else if magic_at(buf, 0, BMP_MAGIC) {
+        Some(ImageFormat::Bmp)
+    } else if magic_at(buf, 0, ICO_MAGIC) {
+        Some(ImageFormat::Ico)
+    } else {
+        None
+    }
+}
+
+/// Check whether the buffer starts with one of the netpbm magic numbers, `P1` to `P6`, which are followed by whitespace.
+fn is_pnm_magic(buf: &[u8]) -> bool {
+    matches!(buf, [b'P', b'1'..=b'6', sep, ..] if sep.is_ascii_whitespace())
+}
+
+/// Check whether the buffer holds the specified magic number at the specified offset.
+fn magic_at(buf: &[u8], offset: usize, magic: &[u8]) -> bool {
+    buf.get(offset..offset + magic.len()) == Some(magic)
+}
+
+/// Load an image from the specified file as the specified format.
+///
+/// Get the image fromat with `guess_format()`.
+///
+/// The file is opened by its path, so the plaintext name is only used in errors,
+/// and paths that aren't valid UTF-8 still load.
+///
+/// # Examples
 ///
 /// ```
-/// # extern crate termimage;
 /// # extern crate image;
-/// # use termimage::util::{ANSI_COLOURS_WHITE_BG, ANSI_COLOUR_ESCAPES, ANSI_BG_COLOUR_ESCAPES, bg_colours_for};
-/// # use termimage::ops::create_colourtable;
+/// # extern crate imgcatr;
+/// # use image::{GenericImageView, ImageFormat};
+/// # use imgcatr::ops::load_image;
+/// # use std::{env, fs};
 /// # fn main() {
-/// # let img = image::DynamicImage::new_rgb8(16, 16);
-/// for line in create_colourtable(&img, &ANSI_COLOURS_WHITE_BG, &bg_colours_for(&ANSI_COLOURS_WHITE_BG)) {
-///     for (upper_clr, lower_clr) in line {
-///         print!("{}{}\u{2580}", // ▀
-///                ANSI_COLOUR_ESCAPES[upper_clr],
-///                ANSI_BG_COLOUR_ESCAPES[lower_clr]);
-///     }
-///     println!("{}{}", ANSI_COLOUR_ESCAPES[15], ANSI_BG_COLOUR_ESCAPES[0]);
-/// }
+/// # #[cfg(unix)] {
+/// # use std::os::unix::ffi::OsStrExt;
+/// # use std::ffi::OsStr;
+/// let path = env::temp_dir().join(OsStr::from_bytes(b"imgcatr-load-\xFF.png"));
+/// fs::copy("assets/rust.png", &path).unwrap();
+///
+/// let img = load_image(&(path.display().to_string(), path.clone()), ImageFormat::Png).unwrap();
+/// assert_eq!(img.dimensions(), (1200, 800));
+/// # fs::remove_file(&path).unwrap();
+/// # }
 /// # }
 /// ```
-pub fn create_colourtable<C: Index<usize, Output = u8>>(img: &DynamicImage, upper_colours: &[C], lower_colours: &[C]) -> Vec<Vec<(usize, usize)>> {
-    let (width, height) = img.dimensions();
-    let term_h = height / 2;
+///
+/// Truncated or otherwise undecodable images are an error, not a panic:
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::ImageFormat;
+/// # use imgcatr::ops::load_image;
+/// # use imgcatr::Error;
+/// # use std::{env, fs};
+/// # fn main() {
+/// let path = env::temp_dir().join("imgcatr-truncated.png");
+/// fs::write(&path, &fs::read("assets/rust.png").unwrap()[..100]).unwrap();
+///
+/// assert_eq!(load_image(&("truncated.png".to_string(), path.clone()), ImageFormat::Png).unwrap_err(),
+///            Error::DecodingImageFailed("truncated.png".to_string()));
+/// # fs::remove_file(&path).unwrap();
+/// # }
+/// ```
+pub fn load_image(file: &(String, PathBuf), format: ImageFormat) -> Result<DynamicImage, Error> {
+    load_image_with_fallback(file, format).map(|(img, _)| img)
+}
 
-    (0..term_h)
-        .map(|y| {
-            let upper_y = y * 2;
-            let lower_y = upper_y + 1;
+/// Load an image from the specified file as the specified format,
+/// retrying once with the format guessed from its magic number if that fails, and return the format that worked.
+///
+/// This is what `load_image()` does, and rescues files with the wrong extension.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::ImageFormat;
+/// # use imgcatr::ops::{guess_format, load_image_with_fallback};
+/// # use std::{env, fs};
+/// # fn main() {
+/// let path = env::temp_dir().join("imgcatr-mislabeled.jpg");
+/// fs::copy("assets/rust.png", &path).unwrap();
+/// let file = (path.display().to_string(), path.clone());
+///
+/// assert_eq!(guess_format(&file), Ok(ImageFormat::Jpeg));
+/// let (_, format) = load_image_with_fallback(&file, ImageFormat::Jpeg).unwrap();
+/// assert_eq!(format, ImageFormat::Png);
+/// # fs::remove_file(&path).unwrap();
+/// # }
+/// ```
+pub fn load_image_with_fallback(file: &(String, PathBuf), format: ImageFormat) -> Result<(DynamicImage, ImageFormat), Error> {
+    load_image_impl(file, format, None)
+}
 
-            (0..width)
-                .map(|x| (closest_colour(img.get_pixel(x, upper_y).to_rgb(), upper_colours), closest_colour(img.get_pixel(x, lower_y).to_rgb(), lower_colours)))
-                .collect()
-        })
-        .collect()
+/// Load an image from the specified file like `load_image_with_fallback()`,
+/// but trade quality for speed by decoding JPEGs at the smallest of 1/8, 1/4, 1/2, or full size that's still at least `min_size`.
+///
+/// Other formats are decoded at full size.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{GenericImageView, ImageFormat, ImageOutputFormat};
+/// # use imgcatr::ops::{load_image, load_image_downscaled};
+/// # use std::{env, fs::File};
+/// # use std::path::PathBuf;
+/// # fn main() {
+/// let path = env::temp_dir().join("imgcatr-downscaled.jpg");
+/// let file = (path.display().to_string(), path.clone());
+/// load_image(&("rust.png".to_string(), PathBuf::from("assets/rust.png")), ImageFormat::Png).unwrap()
+///     .to_rgb8()
+///     .write_to(&mut File::create(&path).unwrap(), ImageOutputFormat::Jpeg(80)).unwrap();
+///
+/// let (img, _) = load_image_downscaled(&file, ImageFormat::Jpeg, (200, 100)).unwrap();
+/// assert_eq!(img.dimensions(), (300, 200));
+/// let (img, _) = load_image_downscaled(&file, ImageFormat::Jpeg, (2000, 2000)).unwrap();
+/// assert_eq!(img.dimensions(), (1200, 800));
+/// # std::fs::remove_file(&path).unwrap();
+/// # }
+/// ```
+pub fn load_image_downscaled(file: &(String, PathBuf), format: ImageFormat, min_size: (u32, u32)) -> Result<(DynamicImage, ImageFormat), Error> {
+    load_image_impl(file, format, Some(min_size))
 }
 
-/// Display the specified image approximating it to the specified colours in the default console using ANSI escape codes.
-pub fn write_ansi<W: Write, C: Index<usize, Output = u8>>(out: &mut W, img: &DynamicImage, foreground_colours: &[C]) {
-    for line in create_colourtable(img, foreground_colours, &bg_colours_for(foreground_colours)) {
-        for (upper_clr, lower_clr) in line {
-            write!(out,
-                   "{}{}\u{2580}", // ▀
-                   ANSI_COLOUR_ESCAPES[upper_clr],
-                   ANSI_BG_COLOUR_ESCAPES[lower_clr])
-                .unwrap();
+/// Load an image from the specified file like `load_image_downscaled()`, but keep memory use proportional to `min_size`
+/// rather than to the image's size where the format allows.
+///
+/// Non-interlaced PNGs are decoded a row at a time, averaging each `n`x`n` block of pixels into one as they go,
+/// for the largest `n` that keeps the image at least `min_size`.
+/// JPEGs are decoded at a reduced size like `load_image_downscaled()` does, and other formats at full size.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{GenericImageView, ImageFormat, Rgba};
+/// # use imgcatr::ops::{load_image, load_image_low_memory};
+/// # use std::path::PathBuf;
+/// # fn main() {
+/// let file = ("rust.png".to_string(), PathBuf::from("assets/rust.png"));
+/// let (img, format) = load_image_low_memory(&file, ImageFormat::Png, (250, 150)).unwrap();
+/// assert_eq!((img.dimensions(), format), ((300, 200), ImageFormat::Png));
+///
+/// // Each pixel is the average of the 4x4 block it replaces
+/// let full = load_image(&file, ImageFormat::Png).unwrap();
+/// let mut sum = [0; 4];
+/// for (x, y) in (0..4).flat_map(|y| (0..4).map(move |x| (x, y))) {
+///     sum.iter_mut().zip(full.get_pixel(600 + x, 400 + y).0).for_each(|(s, c)| *s += c as u32);
+/// }
+/// assert_eq!(img.get_pixel(150, 100), Rgba(sum.map(|s| ((s + 8) / 16) as u8)));
+///
+/// let (img, _) = load_image_low_memory(&file, ImageFormat::Png, (2000, 2000)).unwrap();
+/// assert_eq!(img.dimensions(), (1200, 800));
+/// # }
+/// ```
+pub fn load_image_low_memory(file: &(String, PathBuf), format: ImageFormat, min_size: (u32, u32)) -> Result<(DynamicImage, ImageFormat), Error> {
+    if format == ImageFormat::Png {
+        let f = File::open(&file.1).map_err(|_| Error::OpeningImageFailed(file.0.clone()))?;
+        // Otherwise it's interlaced, so has to be decoded whole, or maybe not a PNG after all, so the fallback has a go
+        if let Ok(Some(img)) = stream_png_downscaled(BufReader::new(f), min_size) {
+            return Ok((img, format));
         }
-        writeln!(out, "{}", ANSI_RESET_ATTRIBUTES).unwrap();
     }
+    load_image_impl(file, format, Some(min_size))
 }
 
-/// Display the specified image in the default console using ANSI 24-bit escape colour codes.
-pub fn write_ansi_truecolor<W: Write>(out: &mut W, img: &DynamicImage) {
-    let (width, height) = img.dimensions();
-    let term_h = height / 2;
+/// Decode the PNG in the specified reader a row at a time, averaging each `n`x`n` block of pixels into one,
+/// for the largest `n` that keeps it at least `min_size`, or get `None` if it's interlaced.
+///
+/// Other bit depths and palettes are expanded to 8-bit greyscale or RGB, with alpha if it has any.
+fn stream_png_downscaled<R: BufRead + Seek>(reader: R, min_size: (u32, u32)) -> Result<Option<DynamicImage>, png::DecodingError> {
+    let mut decoder = png::Decoder::new(reader);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    if reader.info().interlaced {
+        return Ok(None);
+    }
 
-    for y in 0..term_h {
-        let upper_y = y * 2;
-        let lower_y = upper_y + 1;
+    let (width, height) = (reader.info().width, reader.info().height);
+    let channels = reader.output_color_type().0.samples();
+    let factor = (width / min_size.0.max(1)).min(height / min_size.1.max(1)).max(1);
+    let (out_width, out_height) = (width.div_ceil(factor), height.div_ceil(factor));
 
-        for x in 0..width {
-            let upper_pixel = img.get_pixel(x, upper_y).to_rgb();
-            let lower_pixel = img.get_pixel(x, lower_y).to_rgb();
+    let mut sums = vec![0u64; out_width as usize * channels];
+    let mut out = Vec::with_capacity(out_width as usize * out_height as usize * channels);
+    let mut y = 0;
+    while let Some(row) = reader.next_row()? {
+        for (x, px) in row.data().chunks(channels).enumerate() {
+            let sum = &mut sums[x / factor as usize * channels..][..channels];
+            sum.iter_mut().zip(px).for_each(|(s, &c)| *s += c as u64);
+        }
 
-            write!(out,
-                   "\x1B[38;2;{};{};{}m\
-                    \x1B[48;2;{};{};{}m\u{2580}", // ▀
-                   upper_pixel[0],
-                   upper_pixel[1],
-                   upper_pixel[2],
-                   lower_pixel[0],
-                   lower_pixel[1],
-                   lower_pixel[2])
-                .unwrap();
+        y += 1;
+        if y % factor == 0 || y == height {
+            let block_h = (y - 1) % factor + 1;
+            for (out_x, sum) in sums.chunks_mut(channels).enumerate() {
+                let block_w = factor.min(width - out_x as u32 * factor);
+                let count = (block_w * block_h) as u64;
+                out.extend(sum.iter().map(|&s| ((s + count / 2) / count) as u8));
+                sum.iter_mut().for_each(|s| *s = 0);
+            }
         }
-        writeln!(out, "{}", ANSI_RESET_ATTRIBUTES).unwrap();
     }
+
+    Ok(Some(match channels {
+        1 => DynamicImage::ImageLuma8(image::GrayImage::from_raw(out_width, out_height, out).unwrap()),
+        2 => DynamicImage::ImageLumaA8(image::GrayAlphaImage::from_raw(out_width, out_height, out).unwrap()),
+        3 => DynamicImage::ImageRgb8(image::RgbImage::from_raw(out_width, out_height, out).unwrap()),
+        _ => DynamicImage::ImageRgba8(image::RgbaImage::from_raw(out_width, out_height, out).unwrap()),
+    }))
 }
 
-/// Display the specified image in the ascii art style with specified scale.
-pub fn write_ascii(img: &DynamicImage) {
-    println!("{:?}", img.dimensions());
-    let (width,height) = img.dimensions();
-    for y in 0..height{
-        for x in 0..width{
-            if y % 2 == 0 && x % 1 == 0{
-                let pix = img.get_pixel(x,y);
-                let mut intent = pix[0]/3 + pix[1]/3 + pix[2]/3;
-                if pix[3] ==0{
-                    intent = 0;
-                }
-                print!("{}",get_str_ascii(intent));
-            } 
+fn load_image_impl(file: &(String, PathBuf), format: ImageFormat, min_size: Option<(u32, u32)>) -> Result<(DynamicImage, ImageFormat), Error> {
+    let img = decode_image(file, format, min_size)?;
+    if img.is_err() {
+        if let Some(sniffed) = sniff_format(file)?.filter(|&sniffed| sniffed != format) {
+            if let Ok(img) = decode_image(file, sniffed, min_size)? {
+                return Ok((img, sniffed));
+            }
         }
-        if y % 2 ==0{
-            println!("");
+    }
+
+    Ok((img.map_err(|_| Error::DecodingImageFailed(file.0.clone()))?, format))
+}
+
+/// Load an image from the specified seekable reader as the specified format, like `load_image()` does from a file.
+///
+/// Read non-seekable sources into a buffer and use `load_image_from_memory()` instead. The name is only used in errors.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{GenericImageView, ImageFormat};
+/// # use imgcatr::ops::load_image_from_reader;
+/// # use imgcatr::Error;
+/// # use std::io::{BufReader, Cursor};
+/// # use std::fs::{self, File};
+/// # fn main() {
+/// let img = load_image_from_reader(BufReader::new(File::open("assets/rust.png").unwrap()), ImageFormat::Png, "rust.png").unwrap();
+/// assert_eq!(img.dimensions(), (1200, 800));
+///
+/// let buf = fs::read("assets/rust.png").unwrap();
+/// let img = load_image_from_reader(Cursor::new(&buf), ImageFormat::Png, "rust.png").unwrap();
+/// assert_eq!(img.dimensions(), (1200, 800));
+///
+/// assert_eq!(load_image_from_reader(Cursor::new(&buf[..buf.len() / 2]), ImageFormat::Png, "half.png").unwrap_err(),
+///            Error::DecodingImageFailed("half.png".to_string()));
+/// # }
+/// ```
+pub fn load_image_from_reader<R: BufRead + Seek>(reader: R, format: ImageFormat, name: &str) -> Result<DynamicImage, Error> {
+    decode(reader, format, None).map_err(|_| Error::DecodingImageFailed(name.to_string()))
+}
+
+/// Load an image from the specified in-memory buffer, guessing its format from its magic number or footer.
+///
+/// The name is only used in errors.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::GenericImageView;
+/// # use imgcatr::ops::load_image_from_memory;
+/// # use imgcatr::Error;
+/// # use std::fs;
+/// # fn main() {
+/// let img = load_image_from_memory(&fs::read("assets/rust.png").unwrap(), "rust.png").unwrap();
+/// assert_eq!(img.dimensions(), (1200, 800));
+///
+/// assert_eq!(load_image_from_memory(b"not an image", "text").unwrap_err(), Error::GuessingFormatFailed("text".to_string()));
+///
+/// // A 1x1 truecolour TGA, with the TGA 2.0 footer
+/// let mut tga = vec![0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 1, 0, 24, 0, 0x12, 0x34, 0x56];
+/// tga.extend_from_slice(&[0; 8]);
+/// tga.extend_from_slice(b"TRUEVISION-XFILE.\0");
+/// let img = load_image_from_memory(&tga, "tga").unwrap();
+/// assert_eq!(img.dimensions(), (1, 1));
+/// assert_eq!(img.get_pixel(0, 0), image::Rgba([0x56, 0x34, 0x12, 0xFF]));
+/// # }
+/// ```
+///
+/// JPEGs and TIFFs are turned the way their EXIF orientation says to display them:
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{DynamicImage, GenericImageView, ImageOutputFormat, Rgb, RgbImage};
+/// # use imgcatr::ops::load_image_from_memory;
+/// # use std::io::Cursor;
+/// # fn main() {
+/// // Red in the top-left quadrant, blue elsewhere
+/// let img = RgbImage::from_fn(16, 8, |x, y| if x < 8 && y < 4 { Rgb([0xFF, 0x00, 0x00]) } else { Rgb([0x00, 0x00, 0xFF]) });
+/// let mut jpeg = Vec::new();
+/// DynamicImage::ImageRgb8(img).write_to(&mut Cursor::new(&mut jpeg), ImageOutputFormat::Jpeg(100)).unwrap();
+///
+/// // Size, and where red ends up, for each orientation
+/// let expected = [((16, 8), (2, 1)), ((16, 8), (13, 1)), ((16, 8), (13, 6)), ((16, 8), (2, 6)),
+///                 ((8, 16), (1, 2)), ((8, 16), (6, 2)), ((8, 16), (6, 13)), ((8, 16), (1, 13))];
+/// for (orientation, &(size, red)) in (1..=8).zip(&expected) {
+///     // A big-endian TIFF structure with just the orientation in its first directory, in an APP1 segment after APP0
+///     let mut exif = b"\xFF\xE1\x00\x22Exif\x00\x00MM\x00\x2A\x00\x00\x00\x08\x00\x01\x01\x12\x00\x03\x00\x00\x00\x01".to_vec();
+///     exif.extend_from_slice(&[0x00, orientation, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+///     let app0_end = 4 + u16::from_be_bytes([jpeg[4], jpeg[5]]) as usize;
+///     let tagged = [&jpeg[..app0_end], &exif, &jpeg[app0_end..]].concat();
+///
+///     let img = load_image_from_memory(&tagged, "oriented.jpg").unwrap();
+///     assert_eq!(img.dimensions(), size);
+///     assert!(img.get_pixel(red.0, red.1)[0] > 0xC0);
+/// }
+/// # }
+/// ```
+pub fn load_image_from_memory(buf: &[u8], name: &str) -> Result<DynamicImage, Error> {
+    load_image_from_reader(Cursor::new(buf), guess_format_from_memory(buf, name)?, name)
+}
+
+/// Read a single frame, a 4-byte big-endian length followed by that many bytes, from the specified stream.
+///
+/// Returns `None` at the end of the stream, between frames; the index is only used in errors.
+///
+/// # Examples
+///
+/// ```
+/// # use imgcatr::ops::read_length_prefixed;
+/// # use imgcatr::Error;
+/// let mut input = &[0, 0, 0, 2, b'h', b'i', 0, 0, 0, 3, b'h'][..];
+/// assert_eq!(read_length_prefixed(&mut input, 0), Ok(Some(b"hi".to_vec())));
+/// assert_eq!(read_length_prefixed(&mut input, 1), Err(Error::MalformedFrame(1)));
+///
+/// let mut input = &[0, 0, 0, 0][..];
+/// assert_eq!(read_length_prefixed(&mut input, 0), Ok(Some(vec![])));
+/// assert_eq!(read_length_prefixed(&mut input, 1), Ok(None));
+///
+/// assert_eq!(read_length_prefixed(&mut &[0, 0][..], 0), Err(Error::MalformedFrame(0)));
+/// ```
+pub fn read_length_prefixed<R: Read>(input: &mut R, index: usize) -> Result<Option<Vec<u8>>, Error> {
+    let mut len = [0; 4];
+    let mut read = 0;
+    while read < len.len() {
+        match input.read(&mut len[read..]) {
+            Ok(0) if read == 0 => return Ok(None),
+            Ok(0) | Err(_) => return Err(Error::MalformedFrame(index)),
+            Ok(n) => read += n,
         }
     }
+
+    let mut frame = vec![0; u32::from_be_bytes(len) as usize];
+    input.read_exact(&mut frame).map_err(|_| Error::MalformedFrame(index))?;
+    Ok(Some(frame))
 }
 
-fn get_str_ascii(intent :u8)-> &'static str{
-    let index = intent/32;
-    let ascii = [" ",".",",","-","~","+","=","@"];
-    return ascii[index as usize];
+fn decode_image(file: &(String, PathBuf), format: ImageFormat, min_size: Option<(u32, u32)>) -> Result<ImageResult<DynamicImage>, Error> {
+    Ok(decode(BufReader::new(File::open(&file.1).map_err(|_| Error::OpeningImageFailed(file.0.clone()))?),
+              format,
+              min_size))
 }
 
+/// Like `image::load()`, but keeps Radiance HDR images in floating point, for `tonemap_hdr()`,
+/// decodes JPEGs at a reduced size no smaller than `min_size`, if specified, and applies JPEGs' and TIFFs' EXIF orientation.
+fn decode<R: BufRead + Seek>(mut reader: R, format: ImageFormat, min_size: Option<(u32, u32)>) -> ImageResult<DynamicImage> {
+    let start = reader.stream_position()?;
+    let orientation = exif_orientation(&mut reader, format).ok().flatten();
+    reader.seek(SeekFrom::Start(start))?;
+
+    let img = if let (ImageFormat::Jpeg, Some((width, height))) = (format, min_size) {
+        let mut decoder = JpegDecoder::new(reader)?;
+        let (full_width, full_height) = decoder.dimensions();
+        let divisor = [8, 4, 2].into_iter()
+            .find(|&d| full_width.div_ceil(d) >= width && full_height.div_ceil(d) >= height)
+            .unwrap_or(1);
+        decoder.scale(full_width.div_ceil(divisor) as u16, full_height.div_ceil(divisor) as u16)?;
+        DynamicImage::from_decoder(decoder)?
+    } else if format == ImageFormat::Hdr {
+        let decoder = HdrDecoder::new(reader)?;
+        let meta = decoder.metadata();
+        let pixels = decoder.read_image_hdr()?.into_iter().flat_map(|px| px.0).collect();
+        DynamicImage::ImageRgb32F(Rgb32FImage::from_raw(meta.width, meta.height, pixels).unwrap())
+    } else {
+        image::load(reader, format)?
+    };
+    Ok(match orientation {
+        Some(orientation) => apply_orientation(img, orientation),
+        None => img,
+    })
+}
+
+/// Tone-map a high-dynamic-range (floating-point) image, like Radiance HDR and OpenEXR ones, into a displayable 8-bit one.
+///
+/// The linear colours are scaled by `2^exposure`, compressed with the specified operator,
+/// and encoded as sRGB. Alpha is kept. Images that aren't floating-point are returned as-is.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{DynamicImage, Rgb, Rgb32FImage, RgbImage};
+/// # use imgcatr::ops::tonemap_hdr;
+/// # use imgcatr::ToneMapOperator;
+/// # fn main() {
+/// let mut hdr = Rgb32FImage::new(3, 1);
+/// hdr.put_pixel(1, 0, Rgb([1.0, 1.0, 1.0]));
+/// hdr.put_pixel(2, 0, Rgb([1000.0, 0.25, 0.0]));
+/// let hdr = DynamicImage::ImageRgb32F(hdr);
+///
+/// let mapped = tonemap_hdr(&hdr, 0.0, ToneMapOperator::Reinhard).to_rgb8();
+/// assert_eq!(mapped.pixels().map(|px| px.0).collect::<Vec<_>>(), [[0, 0, 0], [188, 188, 188], [255, 124, 0]]);
+/// assert_eq!(tonemap_hdr(&hdr, 1.0, ToneMapOperator::Reinhard).to_rgb8()[(1, 0)], Rgb([213, 213, 213]));
+///
+/// let mapped = tonemap_hdr(&hdr, 0.0, ToneMapOperator::Aces).to_rgb8();
+/// assert_eq!(mapped.pixels().map(|px| px.0).collect::<Vec<_>>(), [[0, 0, 0], [232, 232, 232], [255, 165, 0]]);
+///
+/// let ldr = DynamicImage::ImageRgb8(RgbImage::from_pixel(1, 1, Rgb([1, 2, 3])));
+/// assert_eq!(tonemap_hdr(&ldr, 5.0, ToneMapOperator::Aces), ldr);
+/// # }
+/// ```
+pub fn tonemap_hdr(img: &DynamicImage, exposure: f32, operator: ToneMapOperator) -> DynamicImage {
+    if !matches!(img.color(), ColorType::Rgb32F | ColorType::Rgba32F) {
+        return img.clone();
+    }
+
+    let scale = exposure.exp2();
+    let mut mapped = img.to_rgba32f();
+    let mut out = image::RgbaImage::new(mapped.width(), mapped.height());
+    for (px, out_px) in mapped.pixels_mut().zip(out.pixels_mut()) {
+        for c in 0..3 {
+            let v = (px[c] * scale).max(0.0);
+            out_px[c] = srgb_encode(match operator {
+                ToneMapOperator::Reinhard => v / (1.0 + v),
+                ToneMapOperator::Aces => ((v * (2.51 * v + 0.03)) / (v * (2.43 * v + 0.59) + 0.14)).min(1.0),
+            });
+        }
+        out_px[3] = (px[3].clamp(0.0, 1.0) * 255.0).round() as u8;
+    }
+    DynamicImage::ImageRgba8(out)
+}
+
+/// Composite the specified image over a solid background of the specified colour, so that transparent areas show it.
+///
+/// Images without alpha are returned as-is.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{DynamicImage, GenericImageView, Rgb, Rgba, RgbaImage};
+/// # use imgcatr::ops::flatten_on_background;
+/// # fn main() {
+/// let mut img = RgbaImage::from_pixel(2, 1, Rgba([0xFF, 0x00, 0x00, 0xFF]));
+/// img.put_pixel(1, 0, Rgba([0xFF, 0x00, 0x00, 0x00]));
+///
+/// let flat = flatten_on_background(&DynamicImage::ImageRgba8(img), Rgb([0x00, 0x00, 0xFF]));
+/// assert!(!flat.color().has_alpha());
+/// assert_eq!(flat.get_pixel(0, 0), Rgba([0xFF, 0x00, 0x00, 0xFF]));
+/// assert_eq!(flat.get_pixel(1, 0), Rgba([0x00, 0x00, 0xFF, 0xFF]));
+/// # }
+/// ```
+pub fn flatten_on_background(img: &DynamicImage, bg: Rgb<u8>) -> DynamicImage {
+    if !img.color().has_alpha() {
+        return img.clone();
+    }
+
+    let rgba = img.to_rgba8();
+    DynamicImage::ImageRgb8(image::RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| blend_over(*rgba.get_pixel(x, y), bg)))
+}
+
+/// Adjust the specified image's gamma, then its brightness, then its contrast, leaving alpha untouched.
+///
+/// Each colour channel is raised to `1 / gamma`, then `brightness` is added to it,
+/// then the contrast is changed by `contrast` percent, like `DynamicImage::brighten()` and `DynamicImage::adjust_contrast()`.
+/// At `(0, 0.0, 1.0)` the image is returned as-is.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
+/// # use imgcatr::ops::adjust_image;
+/// # fn main() {
+/// let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 1, Rgba([0x40, 0x80, 0xC0, 0x80])));
+/// assert_eq!(adjust_image(&img, 0, 0.0, 1.0), img);
+///
+/// assert_eq!(adjust_image(&img, 0x10, 0.0, 1.0).get_pixel(0, 0), Rgba([0x50, 0x90, 0xD0, 0x80]));
+/// assert_eq!(adjust_image(&img, 0, 0.0, 2.0).get_pixel(0, 0), Rgba([0x80, 0xB5, 0xDD, 0x80]));
+/// // Gamma applies before brightness, which could otherwise push the channels into different places on the curve.
+/// assert_eq!(adjust_image(&img, 0x10, 0.0, 2.0).get_pixel(0, 0), Rgba([0x90, 0xC5, 0xED, 0x80]));
+/// # }
+/// ```
+pub fn adjust_image(img: &DynamicImage, brightness: i32, contrast: f32, gamma: f32) -> DynamicImage {
+    if brightness == 0 && contrast == 0.0 && gamma == 1.0 {
+        return img.clone();
+    }
+
+    let mut adjusted = img.clone();
+    if gamma != 1.0 {
+        let lut: Vec<u8> = (0..=255).map(|v| ((v as f32 / 255.0).powf(1.0 / gamma) * 255.0).round() as u8).collect();
+        let mut buf = adjusted.to_rgba8();
+        for px in buf.pixels_mut() {
+            for c in 0..3 {
+                px[c] = lut[px[c] as usize];
+            }
+        }
+        adjusted = DynamicImage::ImageRgba8(buf);
+    }
+    if brightness != 0 {
+        adjusted = adjusted.brighten(brightness);
+    }
+    if contrast != 0.0 {
+        adjusted = adjusted.adjust_contrast(contrast);
+    }
+    adjusted
+}
+
+/// Get the image size to downscale to, given its size, the terminal's size and how to size it to the terminal,
+/// along with the region to crop the image to first, if any, which only `ResizeMode::Fill` needs.
+///
+/// The resulting image size is twice as tall as the terminal size because we print two pixels per cell (height-wise),
+/// and cells are taken to be twice as tall as they're wide.
+/// Formats drawing some other amount of pixels per cell, like ASCII art with one, or cells of some other aspect,
+/// take `subcell_resized_size()` with `AnsiOutputFormat::subcells()` instead.
+///
+/// # Examples
+///
+/// ```
+/// # use imgcatr::ops::image_resized_size;
+/// # use imgcatr::{CropRect, ResizeMode};
+/// assert_eq!(image_resized_size((100, 50), (80, 24), ResizeMode::Fit), ((80, 40), None));
+/// assert_eq!(image_resized_size((100, 50), (80, 24), ResizeMode::Stretch), ((80, 48), None));
+/// assert_eq!(image_resized_size((100, 500), (80, 24), ResizeMode::FitWidth), ((80, 400), None));
+/// assert_eq!(image_resized_size((100, 50), (80, 24), ResizeMode::FitHeight), ((96, 48), None));
+/// assert_eq!(image_resized_size((100, 50), (80, 24), ResizeMode::Fill),
+///            ((80, 48), Some(CropRect { x: 8, y: 0, width: 83, height: 50 })));
+/// ```
+pub fn image_resized_size(size: (u32, u32), term_size: (u32, u32), mode: ResizeMode) -> ((u32, u32), Option<CropRect>) {
+    subcell_resized_size(size, term_size, (1, 2), 2.0, mode)
+}
+
+/// Get the image size to downscale to for formats drawing `subcells` pixels per cell, like `image_resized_size()` does for two,
+/// given its size, the terminal's size, how many times taller than wide its cells are, and how to size it to the terminal,
+/// along with the region to crop the image to first, if any.
+///
+/// When preserving the aspect, the image is stretched to account for the subcells not being square.
+///
+/// # Examples
+///
+/// ```
+/// # use imgcatr::ops::{image_resized_size, subcell_resized_size};
+/// # use imgcatr::ResizeMode;
+/// assert_eq!(subcell_resized_size((100, 100), (80, 24), (1, 2), 2.0, ResizeMode::Fit),
+///            image_resized_size((100, 100), (80, 24), ResizeMode::Fit));
+/// assert_eq!(subcell_resized_size((100, 100), (80, 24), (2, 2), 2.0, ResizeMode::Fit), ((96, 48), None));
+/// assert_eq!(subcell_resized_size((100, 100), (80, 24), (1, 1), 2.0, ResizeMode::Fit), ((48, 24), None));
+/// assert_eq!(subcell_resized_size((100, 100), (80, 24), (2, 2), 2.0, ResizeMode::Stretch), ((160, 48), None));
+/// assert_eq!(subcell_resized_size((100, 100), (80, 24), (1, 2), 1.8, ResizeMode::Fit), ((43, 48), None));
+/// ```
+pub fn subcell_resized_size(size: (u32, u32), term_size: (u32, u32), subcells: (u32, u32), cell_aspect: f32, mode: ResizeMode)
+                            -> ((u32, u32), Option<CropRect>) {
+    fit_size(size,
+             (size.0 * subcells.0, ((size.1 * subcells.1) as f32 / cell_aspect) as u32),
+             (term_size.0 * subcells.0, term_size.1 * subcells.1),
+             mode)
+}
+
+/// Get the image size to downscale to for `write_sixel()` and `write_kitty()`, given its size, the terminal's size,
+/// the size of a cell in pixels, and how to size it to the terminal, along with the region to crop the image to first, if any.
+///
+/// Those draw actual pixels, so the terminal size is converted to pixels instead of doubling its height,
+/// assuming 10x20-pixel cells if their size isn't known.
+///
+/// # Examples
+///
+/// ```
+/// # use imgcatr::ops::pixel_resized_size;
+/// # use imgcatr::{CropRect, ResizeMode};
+/// assert_eq!(pixel_resized_size((1000, 1000), (80, 24), Some((8, 16)), ResizeMode::Fit), ((384, 384), None));
+/// assert_eq!(pixel_resized_size((1000, 1000), (80, 24), None, ResizeMode::Stretch), ((800, 480), None));
+/// assert_eq!(pixel_resized_size((1000, 1000), (80, 24), None, ResizeMode::Fill),
+///            ((800, 480), Some(CropRect { x: 0, y: 200, width: 1000, height: 600 })));
+/// ```
+pub fn pixel_resized_size(size: (u32, u32), term_size: (u32, u32), cell_pixels: Option<(u16, u16)>, mode: ResizeMode)
+                          -> ((u32, u32), Option<CropRect>) {
+    let (cell_w, cell_h) = cell_pixels.unwrap_or((10, 20));
+    fit_size(size, size, (term_size.0 * cell_w as u32, term_size.1 * cell_h as u32), mode)
+}
+
+/// Scale the specified size to fit the specified box, or only its width or height, to cover it, or stretch it to it, as per the mode.
+///
+/// To cover it, the box is taken as the target size, and the centre of an image of `source` size,
+/// of which `size` is the aspect-corrected size, is cropped to its aspect.
+fn fit_size(source: (u32, u32), size: (u32, u32), bounds: (u32, u32), mode: ResizeMode) -> ((u32, u32), Option<CropRect>) {
+    let (nwidth, nheight) = bounds;
+    let (width, height) = size;
+
+    let ratio = width as f32 / height as f32;
+    let nratio = nwidth as f32 / nheight as f32;
+
+    match mode {
+        ResizeMode::Stretch => (bounds, None),
+        ResizeMode::Fit => {
+            let scale = if nratio > ratio {
+                nheight as f32 / height as f32
+            } else {
+                nwidth as f32 / width as f32
+            };
+            (((width as f32 * scale) as u32, (height as f32 * scale) as u32), None)
+        }
+        ResizeMode::FitWidth => ((nwidth, ((height as f32 * nwidth as f32 / width as f32) as u32).max(1)), None),
+        ResizeMode::FitHeight => ((((width as f32 * nheight as f32 / height as f32) as u32).max(1), nheight), None),
+        ResizeMode::Fill => {
+            // Fraction of each dimension that's left visible
+            let (visible_w, visible_h) = if nratio > ratio { (1.0, ratio / nratio) } else { (nratio / ratio, 1.0) };
+            let crop_w = ((source.0 as f32 * visible_w).round() as u32).clamp(1, source.0);
+            let crop_h = ((source.1 as f32 * visible_h).round() as u32).clamp(1, source.1);
+            let crop = Some(CropRect {
+                    x: (source.0 - crop_w) / 2,
+                    y: (source.1 - crop_h) / 2,
+                    width: crop_w,
+                    height: crop_h,
+                })
+                .filter(|_| (crop_w, crop_h) != source);
+            (bounds, crop)
+        }
+    }
+}
+
+/// Get the image size to resize to, given its size and a zoom factor, regardless of the terminal's size.
+///
+/// Each resulting pixel takes up half a cell, like with `image_resized_size()`, and neither dimension gets below 1.
+///
+/// # Examples
+///
+/// ```
+/// # use imgcatr::ops::image_scaled_size;
+/// assert_eq!(image_scaled_size((100, 50), 0.5), (50, 25));
+/// assert_eq!(image_scaled_size((100, 50), 2.0), (200, 100));
+/// assert_eq!(image_scaled_size((100, 50), 0.001), (1, 1));
+/// ```
+pub fn image_scaled_size(size: (u32, u32), scale: f32) -> (u32, u32) {
+    let (width, height) = size;
+    ((width as f32 * scale).round().max(1.0) as u32, (height as f32 * scale).round().max(1.0) as u32)
+}
+
+/// Shrink the specified image size to fit inside `max`, preserving its aspect, if it doesn't already.
+///
+/// Apply this to the size from `image_resized_size()` or `image_scaled_size()` to cap how big renders get,
+/// converting `max` from cells to pixels the same way. Neither dimension gets below 1.
+///
+/// # Examples
+///
+/// ```
+/// # use imgcatr::ops::cap_size;
+/// assert_eq!(cap_size((80, 48), (100, 100)), (80, 48));
+/// assert_eq!(cap_size((200, 100), (100, 100)), (100, 50));
+/// assert_eq!(cap_size((200, 100), (30, 100)), (30, 15));
+/// assert_eq!(cap_size((1000, 1), (10, 10)), (10, 1));
+/// ```
+pub fn cap_size(size: (u32, u32), max: (u32, u32)) -> (u32, u32) {
+    if size.0 <= max.0 && size.1 <= max.1 {
+        return size;
+    }
+
+    let scale = (max.0 as f32 / size.0 as f32).min(max.1 as f32 / size.1 as f32);
+    (((size.0 as f32 * scale) as u32).max(1), ((size.1 as f32 * scale) as u32).max(1))
+}
+
+/// Repeat the specified image `cols` times across and `rows` times down, to preview how it tiles.
+///
+/// Size the image to the terminal size divided by the tile counts first, so the whole result still fits.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{DynamicImage, GenericImageView, Rgb, RgbImage};
+/// # use imgcatr::ops::tile_image;
+/// # fn main() {
+/// let mut img = RgbImage::from_pixel(2, 1, Rgb([0x00, 0x00, 0x00]));
+/// img.put_pixel(1, 0, Rgb([0xFF, 0xFF, 0xFF]));
+///
+/// let tiled = tile_image(&DynamicImage::ImageRgb8(img), 3, 2);
+/// assert_eq!(tiled.dimensions(), (6, 2));
+/// assert_eq!(tiled.to_rgb8().pixels().map(|px| px[0]).collect::<Vec<_>>(), [0, 0xFF, 0, 0xFF, 0, 0xFF, 0, 0xFF, 0, 0xFF, 0, 0xFF]);
+/// # }
+/// ```
+pub fn tile_image(img: &DynamicImage, cols: u32, rows: u32) -> DynamicImage {
+    let mut tiled = DynamicImage::new(img.width() * cols, img.height() * rows, img.color());
+    image::imageops::tile(&mut tiled, img);
+    tiled
+}
+
+/// Crop the specified image to the specified region, failing if it doesn't lie within the image.
+///
+/// Crop before sizing the image for the terminal, so that its aspect is the region's.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{DynamicImage, GenericImageView};
+/// # use imgcatr::ops::crop_image;
+/// # use imgcatr::{CropRect, Error};
+/// # fn main() {
+/// let img = DynamicImage::new_rgb8(100, 50);
+/// assert_eq!(crop_image(&img, CropRect { x: 10, y: 10, width: 20, height: 40 }).map(|c| c.dimensions()), Ok((20, 40)));
+///
+/// let rect = CropRect { x: 90, y: 0, width: 20, height: 10 };
+/// assert_eq!(crop_image(&img, rect).map(|c| c.dimensions()), Err(Error::InvalidCrop(rect, (100, 50))));
+/// # }
+/// ```
+pub fn crop_image(img: &DynamicImage, rect: CropRect) -> Result<DynamicImage, Error> {
+    if !rect.fits(img.dimensions()) {
+        return Err(Error::InvalidCrop(rect, img.dimensions()));
+    }
+    Ok(img.crop_imm(rect.x, rect.y, rect.width, rect.height))
+}
+
+/// Resize the specified image to the specified size with the specified filter.
+///
+/// `FilterType::Nearest` keeps pixel art crisp, the others are smoother for photos.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{DynamicImage, GenericImageView, Rgb, RgbImage};
+/// # use image::imageops::FilterType;
+/// # use imgcatr::ops::resize_image;
+/// # fn main() {
+/// let img = DynamicImage::ImageRgb8(RgbImage::from_fn(4, 1, |x, _| Rgb([if x < 2 { 0x00 } else { 0xFF }; 3])));
+/// assert_eq!(resize_image(&img, (2, 1), FilterType::Nearest).to_rgb8().into_raw(), [0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF]);
+///
+/// let smooth = resize_image(&img, (3, 1), FilterType::Triangle);
+/// assert_eq!(smooth.dimensions(), (3, 1));
+/// assert!(![0x00, 0xFF].contains(&smooth.get_pixel(1, 0)[0]));
+/// # }
+/// ```
+pub fn resize_image(img: &DynamicImage, size: (u32, u32), filter: FilterType) -> DynamicImage {
+    img.resize_exact(size.0, size.1, filter)
+}
+
+/// Clip the specified image to at most the specified amount of terminal rows, keeping the top or the centre of it,
+/// or get `None` if it already fits.
+///
+/// Each row holds the specified amount of pixels, two for the half-block writers, one for `write_ansi_truecolor_bg_only()`.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate imgcatr;
+/// # extern crate image;
+/// # use imgcatr::ops::clip_rows;
+/// # use image::GenericImageView;
+/// # fn main() {
+/// let img = image::DynamicImage::new_rgb8(10, 40);
+/// assert_eq!(clip_rows(&img, 5, 2, false).map(|c| c.dimensions()), Some((10, 10)));
+/// assert_eq!(clip_rows(&img, 5, 1, true).map(|c| c.dimensions()), Some((10, 5)));
+/// assert_eq!(clip_rows(&img, 20, 2, true), None);
+/// # }
+/// ```
+pub fn clip_rows(img: &DynamicImage, max_rows: u32, pixels_per_row: u32, centered: bool) -> Option<DynamicImage> {
+    let (width, height) = img.dimensions();
+    let max_height = max_rows * pixels_per_row;
+    if height <= max_height {
+        return None;
+    }
+
+    let top = if centered { (height - max_height) / (2 * pixels_per_row) * pixels_per_row } else { 0 };
+    Some(img.crop_imm(0, top, width, max_height))
+}
+
+/// Get how many columns to left-pad an image `width` cells wide with to center it in a terminal `term_width` columns wide,
+/// `0` if it doesn't fit.
+///
+/// # Examples
+///
+/// ```
+/// # use imgcatr::ops::center_offset;
+/// assert_eq!(center_offset(40, 80), 20);
+/// assert_eq!(center_offset(41, 80), 19);
+/// assert_eq!(center_offset(100, 80), 0);
+/// ```
+pub fn center_offset(width: u32, term_width: u32) -> u32 {
+    term_width.saturating_sub(width) / 2
+}
+
+/// Get how many columns to left-pad and lines to top-pad an image `size` cells big with
+/// to place it at the specified anchor in a terminal `term_size` cells big, `0` along where it doesn't fit.
+///
+/// # Examples
+///
+/// ```
+/// # use imgcatr::ops::anchor_offset;
+/// # use imgcatr::Anchor;
+/// assert_eq!(anchor_offset((40, 10), (80, 24), Anchor::NorthWest), (0, 0));
+/// assert_eq!(anchor_offset((40, 10), (80, 24), Anchor::Center), (20, 7));
+/// assert_eq!(anchor_offset((40, 10), (80, 24), Anchor::SouthEast), (40, 14));
+/// assert_eq!(anchor_offset((100, 10), (80, 24), Anchor::South), (0, 14));
+/// ```
+pub fn anchor_offset(size: (u32, u32), term_size: (u32, u32), anchor: Anchor) -> (u32, u32) {
+    let (column, row) = match anchor {
+        Anchor::NorthWest => (0, 0),
+        Anchor::North => (1, 0),
+        Anchor::NorthEast => (2, 0),
+        Anchor::West => (0, 1),
+        Anchor::Center => (1, 1),
+        Anchor::East => (2, 1),
+        Anchor::SouthWest => (0, 2),
+        Anchor::South => (1, 2),
+        Anchor::SouthEast => (2, 2),
+    };
+    (term_size.0.saturating_sub(size.0) * column / 2, term_size.1.saturating_sub(size.1) * row / 2)
+}
+
+/// Write the specified rendered output, left-padding each of its non-empty lines with `offset` spaces.
+///
+/// The renderers reset the attributes at the end of each line, so the padding is drawn over the default background.
+///
+/// # Examples
+///
+/// ```
+/// # use imgcatr::ops::write_padded;
+/// let mut out = Vec::new();
+/// write_padded(&mut out, b"@+\n\n,.\n", 2);
+/// assert_eq!(out, b"  @+\n\n  ,.\n");
+/// ```
+pub fn write_padded<W: Write>(out: &mut W, rendered: &[u8], offset: u32) {
+    let padding = mul_str(" ", offset as usize);
+    for line in rendered.split_inclusive(|&b| b == b'\n') {
+        if line != b"\n" {
+            out.write_all(padding.as_bytes()).unwrap();
+        }
+        out.write_all(line).unwrap();
+    }
+}
+
+/// Resize the specified image to fit the specified terminal size, as per `image_resized_size()`, with the specified filter,
+/// cropping it first if need be.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{DynamicImage, GenericImageView};
+/// # use image::imageops::FilterType;
+/// # use imgcatr::ops::prepare_image;
+/// # use imgcatr::ResizeMode;
+/// # fn main() {
+/// let img = DynamicImage::new_rgb8(100, 50);
+/// assert_eq!(prepare_image(&img, (20, 20), ResizeMode::Fit, FilterType::Nearest).dimensions(), (20, 10));
+/// assert_eq!(prepare_image(&img, (20, 20), ResizeMode::Stretch, FilterType::Triangle).dimensions(), (20, 40));
+/// assert_eq!(prepare_image(&img, (20, 20), ResizeMode::Fill, FilterType::Triangle).dimensions(), (20, 40));
+/// # }
+/// ```
+pub fn prepare_image(img: &DynamicImage, term_size: (u32, u32), mode: ResizeMode, filter: FilterType) -> DynamicImage {
+    let (size, crop) = image_resized_size(img.dimensions(), term_size, mode);
+    match crop {
+        Some(rect) => img.crop_imm(rect.x, rect.y, rect.width, rect.height).resize_exact(size.0, size.1, filter),
+        None => img.resize_exact(size.0, size.1, filter),
+    }
+}
+
+/// Add light noise to each colour channel of the specified image, to break up banding in smooth gradients.
+///
+/// The noise is triangular, of at most 2 levels either way, and depends only on the seed and the pixel's position,
+/// so the same seed always gives the same output. Alpha is left untouched.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{DynamicImage, Rgba, RgbaImage};
+/// # use imgcatr::ops::dither_noise;
+/// # fn main() {
+/// let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(16, 16, Rgba([0x80, 0x80, 0x80, 0x40])));
+/// let dithered = dither_noise(&img, 1).to_rgba8();
+///
+/// assert_eq!(dithered, dither_noise(&img, 1).to_rgba8());
+/// assert_ne!(dithered, dither_noise(&img, 2).to_rgba8());
+/// assert!(dithered.pixels().all(|px| (0x7E..=0x82).contains(&px[0]) && px[3] == 0x40));
+/// # }
+/// ```
+pub fn dither_noise(img: &DynamicImage, seed: u64) -> DynamicImage {
+    let mut buf = img.to_rgba8();
+    let width = buf.width() as u64;
+    for (x, y, px) in buf.enumerate_pixels_mut() {
+        let hash = splitmix64(seed ^ splitmix64(y as u64 * width + x as u64));
+        for c in 0..3 {
+            let noise = ((hash >> (c * 16)) as u8 as i32 - (hash >> (c * 16 + 8)) as u8 as i32) * 2 / 255;
+            px[c] = (px[c] as i32 + noise).clamp(0, 255) as u8;
+        }
+    }
+    DynamicImage::ImageRgba8(buf)
+}
+
+/// Dither the specified image to the specified colours with Floyd–Steinberg error diffusion,
+/// snapping each pixel to its closest colour and spreading the difference over the pixels not yet snapped.
+///
+/// Even rows end up as upper pixels and odd ones as lower pixels of `create_colourtable()`'s cells,
+/// so they're snapped to the respective colours. Alpha is left untouched.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{DynamicImage, Rgb, RgbImage};
+/// # use imgcatr::ops::dither_floyd_steinberg;
+/// # fn main() {
+/// let palette = [Rgb([0x00, 0x00, 0x00]), Rgb([0xFF, 0xFF, 0xFF])];
+/// let grey = DynamicImage::ImageRgb8(RgbImage::from_pixel(8, 8, Rgb([0x80, 0x80, 0x80])));
+/// let dithered = dither_floyd_steinberg(&grey, &palette, &palette).to_rgb8();
+///
+/// // Mid-grey comes out as a mix of about as many black as white pixels.
+/// let white = dithered.pixels().filter(|&&px| px == palette[1]).count();
+/// assert!(dithered.pixels().all(|px| palette.contains(px)));
+/// assert!((28..=36).contains(&white));
+/// # }
+/// ```
+pub fn dither_floyd_steinberg<C: Index<usize, Output = u8>>(img: &DynamicImage, upper_colours: &[C], lower_colours: &[C]) -> DynamicImage {
+    let mut buf = img.to_rgba8();
+    let (width, height) = (buf.width() as usize, buf.height() as usize);
+    let mut errors = vec![[0f32; 3]; width * (height + 1) + 1];
+
+    for y in 0..height {
+        let colours = if y % 2 == 0 { upper_colours } else { lower_colours };
+        for x in 0..width {
+            let px = buf.get_pixel_mut(x as u32, y as u32);
+            let err = errors[y * width + x];
+            let wanted = [0, 1, 2].map(|c| (px[c] as f32 + err[c]).clamp(0.0, 255.0));
+            let clr = &colours[closest_colour(Rgb(wanted.map(|v| v.round() as u8)), colours)];
+
+            for c in 0..3 {
+                px[c] = clr[c];
+                let diff = wanted[c] - clr[c] as f32;
+                if x + 1 < width {
+                    errors[y * width + x + 1][c] += diff * 7.0 / 16.0;
+                }
+                if x > 0 {
+                    errors[(y + 1) * width + x - 1][c] += diff * 3.0 / 16.0;
+                }
+                errors[(y + 1) * width + x][c] += diff * 5.0 / 16.0;
+                if x + 1 < width {
+                    errors[(y + 1) * width + x + 1][c] += diff * 1.0 / 16.0;
+                }
+            }
+        }
+    }
+    DynamicImage::ImageRgba8(buf)
+}
+
+/// [SplitMix64](https://prng.di.unimi.it/splitmix64.c) finaliser, a cheap but thoroughly mixing hash.
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+/// Keep only every `step`th frame of an animation, starting with the first one.
+///
+/// Each kept frame's delay is extended by those of the frames dropped after it, so the total duration stays the same.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{Delay, Frame, RgbaImage};
+/// # use imgcatr::ops::step_frames;
+/// # use std::time::Duration;
+/// # fn main() {
+/// let frames = (0..5).map(|_| Frame::from_parts(RgbaImage::new(1, 1), 0, 0, Delay::from_numer_denom_ms(10, 1))).collect();
+/// let delays = step_frames(frames, 2).into_iter().map(|f| Duration::from(f.delay())).collect::<Vec<_>>();
+/// assert_eq!(delays, [Duration::from_millis(20), Duration::from_millis(20), Duration::from_millis(10)]);
+/// # }
+/// ```
+pub fn step_frames(frames: Vec<Frame>, step: usize) -> Vec<Frame> {
+    let mut frames = frames.into_iter();
+    let mut stepped = Vec::with_capacity(frames.len() / step.max(1) + 1);
+    while let Some(frame) = frames.next() {
+        let delay = frames.by_ref().take(step.max(1) - 1).fold(Duration::from(frame.delay()), |acc, f| acc + Duration::from(f.delay()));
+        let (left, top) = (frame.left(), frame.top());
+        stepped.push(Frame::from_parts(frame.into_buffer(), left, top, Delay::from_saturating_duration(delay)));
+    }
+    stepped
+}
+
+/// Synthesise `factor - 1` crossfaded frames between each pair of consecutive frames of an animation, for smoother playback.
+///
+/// The delay of each original frame is split evenly between it and the frames blended after it.
+/// The last frame is kept as-is, as are pairs of frames of differing sizes.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{Delay, Frame, Rgba, RgbaImage};
+/// # use imgcatr::ops::interpolate_frames;
+/// # use std::time::Duration;
+/// # fn main() {
+/// let frame = |clr| Frame::from_parts(RgbaImage::from_pixel(1, 1, Rgba(clr)), 0, 0, Delay::from_numer_denom_ms(100, 1));
+/// let frames = interpolate_frames(vec![frame([0x00, 0x00, 0x00, 0xFF]), frame([0xFF, 0xFF, 0xFF, 0xFF])], 2);
+///
+/// assert_eq!(frames.iter().map(|f| f.buffer()[(0, 0)][0]).collect::<Vec<_>>(), [0x00, 0x80, 0xFF]);
+/// assert_eq!(frames.iter().map(|f| Duration::from(f.delay())).collect::<Vec<_>>(),
+///            [Duration::from_millis(50), Duration::from_millis(50), Duration::from_millis(100)]);
+/// # }
+/// ```
+pub fn interpolate_frames(frames: Vec<Frame>, factor: u32) -> Vec<Frame> {
+    if factor <= 1 || frames.len() < 2 {
+        return frames;
+    }
+
+    let mut interpolated = Vec::with_capacity(frames.len() * factor as usize);
+    let mut frames = frames.into_iter().peekable();
+    while let Some(frame) = frames.next() {
+        let next = match frames.peek() {
+            Some(next) if next.buffer().dimensions() == frame.buffer().dimensions() => next,
+            _ => {
+                interpolated.push(frame);
+                continue;
+            }
+        };
+
+        let delay = Delay::from_saturating_duration(Duration::from(frame.delay()) / factor);
+        let blended = (1..factor)
+            .map(|step| {
+                let t = step as f32 / factor as f32;
+                let mut buf = frame.buffer().clone();
+                for (px, &next_px) in buf.pixels_mut().zip(next.buffer().pixels()) {
+                    *px = blend(*px, next_px, t);
+                }
+                Frame::from_parts(buf, frame.left(), frame.top(), delay)
+            })
+            .collect::<Vec<_>>();
+
+        let (left, top) = (frame.left(), frame.top());
+        interpolated.push(Frame::from_parts(frame.into_buffer(), left, top, delay));
+        interpolated.extend(blended);
+    }
+    interpolated
+}
+
+/// Decode all frames of the specified GIF file, each composited onto the full canvas.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{Delay, Frame, Rgba, RgbaImage};
+/// # use image::codecs::gif::GifEncoder;
+/// # use imgcatr::ops::load_gif_frames;
+/// # use std::{env, fs::{self, File}};
+/// # fn main() {
+/// let path = env::temp_dir().join("imgcatr-frames.gif");
+/// let frame = |clr| Frame::from_parts(RgbaImage::from_pixel(4, 2, Rgba(clr)), 0, 0, Delay::from_numer_denom_ms(20, 1));
+/// GifEncoder::new(File::create(&path).unwrap())
+///     .encode_frames(vec![frame([0xFF, 0x00, 0x00, 0xFF]), frame([0x00, 0x00, 0xFF, 0xFF])])
+///     .unwrap();
+///
+/// let frames = load_gif_frames(&(path.display().to_string(), path.clone())).unwrap();
+/// assert_eq!(frames.len(), 2);
+/// assert_eq!(frames[1].buffer().dimensions(), (4, 2));
+/// assert_eq!(frames[1].buffer()[(0, 0)], Rgba([0x00, 0x00, 0xFF, 0xFF]));
+/// # fs::remove_file(&path).unwrap();
+/// # }
+/// ```
+pub fn load_gif_frames(file: &(String, PathBuf)) -> Result<Vec<Frame>, Error> {
+    let f = File::open(&file.1).map_err(|_| Error::OpeningImageFailed(file.0.clone()))?;
+    GifDecoder::new(BufReader::new(f))
+        .and_then(|decoder| decoder.into_frames().collect_frames())
+        .map_err(|_| Error::DecodingImageFailed(file.0.clone()))
+}
+
+/// Decode all frames of the specified animated WebP file, each composited onto the full canvas, like `load_gif_frames()`.
+///
+/// Still WebPs have no frames, so load those with `load_image()` instead.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{ColorType, Rgba, RgbaImage};
+/// # use image::codecs::webp::WebPEncoder;
+/// # use imgcatr::ops::load_webp_frames;
+/// # use std::{env, fs::{self, File}};
+/// # fn main() {
+/// let path = env::temp_dir().join("imgcatr-still.webp");
+/// let img = RgbaImage::from_pixel(4, 2, Rgba([0xFF, 0x00, 0x00, 0xFF]));
+/// WebPEncoder::new_lossless(File::create(&path).unwrap()).encode(&img, 4, 2, ColorType::Rgba8).unwrap();
+///
+/// assert!(load_webp_frames(&(path.display().to_string(), path.clone())).unwrap().is_empty());
+/// # fs::remove_file(&path).unwrap();
+/// # }
+/// ```
+pub fn load_webp_frames(file: &(String, PathBuf)) -> Result<Vec<Frame>, Error> {
+    let f = File::open(&file.1).map_err(|_| Error::OpeningImageFailed(file.0.clone()))?;
+    WebPDecoder::new(BufReader::new(f))
+        .and_then(|decoder| if decoder.has_animation() {
+            decoder.into_frames().collect_frames()
+        } else {
+            Ok(Vec::new())
+        })
+        .map_err(|_| Error::DecodingImageFailed(file.0.clone()))
+}
+
+/// Decode all frames of the specified animated GIF or WebP file, with `load_gif_frames()` or `load_webp_frames()`,
+/// or none for still WebPs and other formats.
+pub fn load_frames(file: &(String, PathBuf), format: ImageFormat) -> Result<Vec<Frame>, Error> {
+    match format {
+        ImageFormat::Gif => load_gif_frames(file),
+        ImageFormat::WebP => load_webp_frames(file),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Create a line-major table of (upper, lower) colour approximation indices given the supported colours therefor.
+///
+/// The last line of an odd-height image only has upper pixels, so its lower indices approximate them instead.
+///
+/// # Examples
+///
+/// Approximate `img` to ANSI and display it to stdout.
+///
+/// ```
+/// # extern crate imgcatr;
+/// # extern crate image;
+/// # use imgcatr::util::{ANSI_COLOURS_WHITE_BG, ANSI_COLOUR_ESCAPES, ANSI_BG_COLOUR_ESCAPES, bg_colours_for};
+/// # use imgcatr::ops::create_colourtable;
+/// # fn main() {
+/// # let img = image::DynamicImage::new_rgb8(16, 16);
+/// for line in create_colourtable(&img, &ANSI_COLOURS_WHITE_BG, &bg_colours_for(&ANSI_COLOURS_WHITE_BG)) {
+///     for (upper_clr, lower_clr) in line {
+///         print!("{}{}\u{2580}", // ▀
+///                ANSI_COLOUR_ESCAPES[upper_clr],
+///                ANSI_BG_COLOUR_ESCAPES[lower_clr]);
+///     }
+///     println!("{}{}", ANSI_COLOUR_ESCAPES[15], ANSI_BG_COLOUR_ESCAPES[0]);
+/// }
+/// # }
+/// ```
+///
+/// 8-bit RGB(A) images are read straight from their buffers and others pixel by pixel, with the same result.
+///
+/// ```
+/// # extern crate imgcatr;
+/// # extern crate image;
+/// # use image::{DynamicImage, Rgb, RgbImage};
+/// # use imgcatr::util::ANSI_COLOURS_BLACK_BG;
+/// # use imgcatr::ops::create_colourtable;
+/// # fn main() {
+/// let img = DynamicImage::ImageRgb8(RgbImage::from_fn(16, 16, |x, y| Rgb([x as u8 * 16, y as u8 * 16, 0xCD])));
+/// let table = create_colourtable(&img, &ANSI_COLOURS_BLACK_BG, &ANSI_COLOURS_BLACK_BG);
+///
+/// assert_eq!(table, create_colourtable(&DynamicImage::ImageRgba8(img.to_rgba8()), &ANSI_COLOURS_BLACK_BG, &ANSI_COLOURS_BLACK_BG));
+/// assert_eq!(table, create_colourtable(&DynamicImage::ImageRgb16(img.to_rgb16()), &ANSI_COLOURS_BLACK_BG, &ANSI_COLOURS_BLACK_BG));
+/// # }
+/// ```
+pub fn create_colourtable<C: Index<usize, Output = u8>>(img: &DynamicImage, upper_colours: &[C], lower_colours: &[C]) -> Vec<Vec<(usize, usize)>> {
+    let (width, height) = img.dimensions();
+    if (width * height.div_ceil(2)) as usize > mem::size_of::<ColourLut>() && upper_colours.len() <= 256 && lower_colours.len() <= 256 {
+        let upper_lut = build_colour_lut(upper_colours);
+        let lower_lut = build_colour_lut(lower_colours);
+        let lookup = |lut: &ColourLut, px: Rgb<u8>| lut[(px[0] >> 3) as usize][(px[1] >> 3) as usize][(px[2] >> 3) as usize] as usize;
+        colourtable_lines(img, |upper, lower| (lookup(&upper_lut, upper), lookup(&lower_lut, lower)))
+    } else {
+        // C isn't necessarily Sync, so share copies across threads
+        #[cfg(feature = "rayon")]
+        let (upper_copy, lower_copy) = (upper_colours.iter().map(|c| Rgb([c[0], c[1], c[2]])).collect::<Vec<_>>(),
+                                        lower_colours.iter().map(|c| Rgb([c[0], c[1], c[2]])).collect::<Vec<_>>());
+        #[cfg(feature = "rayon")]
+        let (upper_colours, lower_colours) = (&upper_copy[..], &lower_copy[..]);
+        colourtable_lines(img, |upper, lower| (closest_colour(upper, upper_colours), closest_colour(lower, lower_colours)))
+    }
+}
+
+/// Approximate each (upper, lower) pixel pair with the specified function.
+#[cfg(not(feature = "rayon"))]
+fn colourtable_lines<F: Fn(Rgb<u8>, Rgb<u8>) -> (usize, usize)>(img: &DynamicImage, approximate: F) -> Vec<Vec<(usize, usize)>> {
+    (0..img.height().div_ceil(2)).map(|y| colourtable_line(img, y, &approximate)).collect()
+}
+
+/// Approximate each (upper, lower) pixel pair with the specified function, a line per task.
+#[cfg(feature = "rayon")]
+fn colourtable_lines<F: Fn(Rgb<u8>, Rgb<u8>) -> (usize, usize) + Sync>(img: &DynamicImage, approximate: F) -> Vec<Vec<(usize, usize)>> {
+    use rayon::prelude::*;
+
+    (0..img.height().div_ceil(2)).into_par_iter().map(|y| colourtable_line(img, y, &approximate)).collect()
+}
+
+fn colourtable_line<F: Fn(Rgb<u8>, Rgb<u8>) -> (usize, usize)>(img: &DynamicImage, y: u32, approximate: &F) -> Vec<(usize, usize)> {
+    let pixels = RgbPixels::new(img);
+    let upper_y = y * 2;
+    let lower_y = (upper_y + 1).min(img.height() - 1);
+
+    (0..img.width())
+        .map(|x| approximate(pixels.get(x, upper_y), pixels.get(x, lower_y)))
+        .collect()
+}
+
+/// Reader of an image's pixels as RGB, indexing the samples of 8-bit RGB(A) buffers directly,
+/// since going through `DynamicImage::get_pixel()` dispatches and bounds-checks every time.
+enum RgbPixels<'a> {
+    Raw {
+        samples: &'a [u8],
+        width: usize,
+        channels: usize,
+    },
+    Dynamic(&'a DynamicImage),
+}
+
+impl<'a> RgbPixels<'a> {
+    fn new(img: &'a DynamicImage) -> RgbPixels<'a> {
+        match img {
+            DynamicImage::ImageRgb8(buf) => RgbPixels::Raw { samples: buf.as_raw(), width: buf.width() as usize, channels: 3 },
+            DynamicImage::ImageRgba8(buf) => RgbPixels::Raw { samples: buf.as_raw(), width: buf.width() as usize, channels: 4 },
+            img => RgbPixels::Dynamic(img),
+        }
+    }
+
+    #[inline]
+    fn get(&self, x: u32, y: u32) -> Rgb<u8> {
+        match *self {
+            RgbPixels::Raw { samples, width, channels } => {
+                let offset = (y as usize * width + x as usize) * channels;
+                Rgb([samples[offset], samples[offset + 1], samples[offset + 2]])
+            }
+            RgbPixels::Dynamic(img) => img.get_pixel(x, y).to_rgb(),
+        }
+    }
+}
+
+/// Create a line-major table of (foreground, background, eighths) approximations given the supported colours therefor,
+/// approximating each cell's average colour by drawing the lower `eighths` of it in the foreground colour over the background one.
+///
+/// The foreground and background are the closest colours to the average out of their sets, avoiding picking the same one twice,
+/// and the split is the one that best blends them into the average.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate imgcatr;
+/// # extern crate image;
+/// # use imgcatr::util::{ANSI_COLOURS_BLACK_BG, bg_colours_for};
+/// # use imgcatr::ops::create_interpolated_colourtable;
+/// # fn main() {
+/// let red = image::DynamicImage::ImageRgb8(image::RgbImage::from_pixel(2, 2, image::Rgb([0xCD, 0x00, 0x00])));
+/// let table = create_interpolated_colourtable(&red, &ANSI_COLOURS_BLACK_BG, bg_colours_for(&ANSI_COLOURS_BLACK_BG));
+/// // Red is in the palette, so it fills the whole cell, over the next closest colour, magenta
+/// assert_eq!(table, vec![vec![(1, 5, 8), (1, 5, 8)]]);
+///
+/// // An odd last row gets a line of its own, like with `create_colourtable()`
+/// let red = image::DynamicImage::ImageRgb8(image::RgbImage::from_pixel(1, 3, image::Rgb([0xCD, 0x00, 0x00])));
+/// let table = create_interpolated_colourtable(&red, &ANSI_COLOURS_BLACK_BG, bg_colours_for(&ANSI_COLOURS_BLACK_BG));
+/// assert_eq!(table, vec![vec![(1, 5, 8)], vec![(1, 5, 8)]]);
+/// # }
+/// ```
+pub fn create_interpolated_colourtable<C: Index<usize, Output = u8>>(img: &DynamicImage, upper_colours: &[C], lower_colours: &[C])
+                                                                     -> Vec<Vec<(usize, usize, usize)>> {
+    let (width, height) = img.dimensions();
+    let term_h = height.div_ceil(2);
+    let rgb_of = |c: &C| Rgba([c[0], c[1], c[2], 0xFF]);
+
+    (0..term_h)
+        .map(|y| {
+            (0..width)
+                .map(|x| {
+                    let upper = img.get_pixel(x, y * 2).to_rgb();
+                    let lower = img.get_pixel(x, (y * 2 + 1).min(height - 1)).to_rgb();
+                    let target = Rgb([(upper[0] as u16 + lower[0] as u16).div_ceil(2) as u8,
+                                      (upper[1] as u16 + lower[1] as u16).div_ceil(2) as u8,
+                                      (upper[2] as u16 + lower[2] as u16).div_ceil(2) as u8]);
+
+                    let fg = closest_colour(target, upper_colours);
+                    let bg = (0..lower_colours.len())
+                        .filter(|&i| rgb_of(&lower_colours[i]) != rgb_of(&upper_colours[fg]))
+                        .map(|i| (colour_distance(target, &lower_colours[i]), i))
+                        .min_by(|&(lhs_diff, _), &(rhs_diff, _)| lhs_diff.partial_cmp(&rhs_diff).unwrap())
+                        .map(|(_, i)| i)
+                        .unwrap_or_else(|| closest_colour(target, lower_colours));
+
+                    let (fg_rgb, bg_rgb) = (rgb_of(&upper_colours[fg]), rgb_of(&lower_colours[bg]));
+                    let eighths = (0..LOWER_EIGHTH_BLOCKS.len())
+                        .map(|e| (colour_distance(target, &blend(bg_rgb, fg_rgb, e as f32 / 8.0).to_rgb()), e))
+                        .min_by(|&(lhs_diff, _), &(rhs_diff, _)| lhs_diff.partial_cmp(&rhs_diff).unwrap())
+                        .unwrap()
+                        .1;
+                    (fg, bg, eighths)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Display the specified image as `create_interpolated_colourtable()` approximates it to the specified colours
+/// in the default console using ANSI escape codes, with 256-colour background escapes if `wide_bg`, like `write_ansi_wide_bg()`.
+pub fn write_ansi_interpolated<W: Write, C: Index<usize, Output = u8>>(out: &mut W, img: &DynamicImage, foreground_colours: &[C], wide_bg: bool) {
+    let background_colours = if wide_bg {
+        foreground_colours
+    } else {
+        bg_colours_for(foreground_colours)
+    };
+
+    for line in create_interpolated_colourtable(img, foreground_colours, background_colours) {
+        for (fg, bg, eighths) in line {
+            write!(out,
+                   "{}{}{}",
+                   ANSI_COLOUR_ESCAPES[fg],
+                   if wide_bg { ANSI_BG_COLOUR_ESCAPES_16[bg] } else { ANSI_BG_COLOUR_ESCAPES[bg] },
+                   LOWER_EIGHTH_BLOCKS[eighths])
+                .unwrap();
+        }
+        writeln!(out, "{}", ANSI_RESET_ATTRIBUTES).unwrap();
+    }
+}
+
+/// Get the mean `colour_distance()` between the image's pixels and their approximations by `create_colourtable()`
+/// with the specified colours, as displayed by `write_ansi()` or `write_ansi_wide_bg()`.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate imgcatr;
+/// # extern crate image;
+/// # use imgcatr::util::ANSI_COLOURS_BLACK_BG;
+/// # use imgcatr::ops::palette_error;
+/// # fn main() {
+/// let black = image::DynamicImage::new_rgb8(4, 4);
+/// assert_eq!(palette_error(&black, &ANSI_COLOURS_BLACK_BG, &ANSI_COLOURS_BLACK_BG), 0.0);
+///
+/// let grey = image::DynamicImage::ImageRgb8(image::RgbImage::from_pixel(4, 4, image::Rgb([0x40, 0x40, 0x40])));
+/// assert!(palette_error(&grey, &ANSI_COLOURS_BLACK_BG, &ANSI_COLOURS_BLACK_BG) > 0.0);
+/// # }
+/// ```
+pub fn palette_error<C: Index<usize, Output = u8>>(img: &DynamicImage, upper_colours: &[C], lower_colours: &[C]) -> f32 {
+    let table = create_colourtable(img, upper_colours, lower_colours);
+
+    let mut total = 0.0;
+    let mut count = 0;
+    for (y, line) in table.into_iter().enumerate() {
+        for (x, (upper_clr, lower_clr)) in line.into_iter().enumerate() {
+            total += colour_distance(img.get_pixel(x as u32, y as u32 * 2).to_rgb(), &upper_colours[upper_clr]);
+            count += 1;
+            if y as u32 * 2 + 1 < img.height() {
+                total += colour_distance(img.get_pixel(x as u32, y as u32 * 2 + 1).to_rgb(), &lower_colours[lower_clr]);
+                count += 1;
+            }
+        }
+    }
+
+    if count == 0 { 0.0 } else { total / count as f32 }
+}
+
+/// Display the specified image approximating it to the specified colours in the default console using ANSI escape codes.
+///
+/// The last line of an odd-height image is drawn over the default background.
+pub fn write_ansi<W: Write, C: Index<usize, Output = u8>>(out: &mut W, img: &DynamicImage, foreground_colours: &[C]) {
+    for (y, line) in create_colourtable(img, foreground_colours, bg_colours_for(foreground_colours)).into_iter().enumerate() {
+        let has_lower = (y as u32 * 2 + 1) < img.height();
+        for (upper_clr, lower_clr) in line {
+            write!(out,
+                   "{}{}\u{2580}", // ▀
+                   ANSI_COLOUR_ESCAPES[upper_clr],
+                   if has_lower { ANSI_BG_COLOUR_ESCAPES[lower_clr] } else { "" })
+                .unwrap();
+        }
+        writeln!(out, "{}", ANSI_RESET_ATTRIBUTES).unwrap();
+    }
+}
+
+/// Display the specified image approximating it to the fixed colours of the xterm 256-colour palette
+/// in the default console using ANSI escape codes.
+///
+/// The 16 basic colours are skipped, as their actual values depend on the terminal's theme.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{DynamicImage, Rgb, RgbImage};
+/// # use imgcatr::ops::write_ansi_256;
+/// # fn main() {
+/// let mut img = RgbImage::from_pixel(2, 2, Rgb([0xFF, 0x00, 0x00]));
+/// img.put_pixel(0, 1, Rgb([0x80, 0x80, 0x80]));
+///
+/// let mut out = Vec::new();
+/// write_ansi_256(&mut out, &DynamicImage::ImageRgb8(img));
+/// assert_eq!(String::from_utf8(out).unwrap(),
+///            "\x1B[38;5;196m\x1B[48;5;244m\u{2580}\x1B[38;5;196m\x1B[48;5;196m\u{2580}\x1B[0m\n");
+/// # }
+/// ```
+pub fn write_ansi_256<W: Write>(out: &mut W, img: &DynamicImage) {
+    let colours = &ANSI_COLOURS_256[ANSI_256_FIXED_START..];
+    for (y, line) in create_colourtable(img, colours, colours).into_iter().enumerate() {
+        let has_lower = (y as u32 * 2 + 1) < img.height();
+        for (upper_clr, lower_clr) in line {
+            write!(out, "\x1B[38;5;{}m", ANSI_256_FIXED_START + upper_clr).unwrap();
+            if has_lower {
+                write!(out, "\x1B[48;5;{}m", ANSI_256_FIXED_START + lower_clr).unwrap();
+            }
+            write!(out, "\u{2580}").unwrap(); // ▀
+        }
+        writeln!(out, "{}", ANSI_RESET_ATTRIBUTES).unwrap();
+    }
+}
+
+/// Display the specified image approximating it to the specified colours in the default console using ANSI escape codes,
+/// like `write_ansi()`, but with 256-colour background escapes, so the lower pixels can be any of the 16 colours as well.
+pub fn write_ansi_wide_bg<W: Write, C: Index<usize, Output = u8>>(out: &mut W, img: &DynamicImage, foreground_colours: &[C]) {
+    for (y, line) in create_colourtable(img, foreground_colours, foreground_colours).into_iter().enumerate() {
+        let has_lower = (y as u32 * 2 + 1) < img.height();
+        for (upper_clr, lower_clr) in line {
+            write!(out,
+                   "{}{}\u{2580}", // ▀
+                   ANSI_COLOUR_ESCAPES[upper_clr],
+                   if has_lower { ANSI_BG_COLOUR_ESCAPES_16[lower_clr] } else { "" })
+                .unwrap();
+        }
+        writeln!(out, "{}", ANSI_RESET_ATTRIBUTES).unwrap();
+    }
+}
+
+/// Display the specified image in the default console using ANSI 24-bit escape colour codes.
+///
+/// The last line of an odd-height image is drawn over the default background.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{DynamicImage, Rgb, RgbImage};
+/// # use imgcatr::ops::write_ansi_truecolor;
+/// # fn main() {
+/// let img = RgbImage::from_fn(1, 3, |_, y| Rgb([[0xFF, 0x00, 0x00], [0x00, 0xFF, 0x00], [0x00, 0x00, 0xFF]][y as usize]));
+///
+/// let mut out = Vec::new();
+/// write_ansi_truecolor(&mut out, &DynamicImage::ImageRgb8(img));
+/// assert_eq!(String::from_utf8(out).unwrap(),
+///            "\x1B[38;2;255;0;0m\x1B[48;2;0;255;0m\u{2580}\x1B[0m\n\
+///             \x1B[38;2;0;0;255m\u{2580}\x1B[0m\n");
+/// # }
+/// ```
+///
+/// 8-bit RGB(A) images are read straight from their buffers and others pixel by pixel, with the same result.
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{DynamicImage, Rgba, RgbaImage};
+/// # use imgcatr::ops::write_ansi_truecolor;
+/// # fn main() {
+/// let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(5, 4, |x, y| Rgba([x as u8 * 60, y as u8 * 80, 0x7F, 0xFF])));
+/// let output = |img: &DynamicImage| {
+///     let mut out = Vec::new();
+///     write_ansi_truecolor(&mut out, img);
+///     out
+/// };
+///
+/// assert!(output(&img).starts_with(b"\x1B[38;2;0;0;127m\x1B[48;2;0;80;127m\xE2\x96\x80"));
+/// assert_eq!(output(&img), output(&DynamicImage::ImageRgb8(img.to_rgb8())));
+/// assert_eq!(output(&img), output(&DynamicImage::ImageRgba16(img.to_rgba16())));
+/// # }
+/// ```
+///
+/// The output is the same as formatting each cell with `write!()`, for every channel value and odd heights.
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{DynamicImage, Rgb, RgbImage};
+/// # use imgcatr::ops::write_ansi_truecolor;
+/// # use std::fmt::Write;
+/// # fn main() {
+/// let img = RgbImage::from_fn(256, 3, |x, y| Rgb([x as u8, (x as u8).wrapping_mul(7), (y * 127) as u8]));
+///
+/// let mut expected = String::new();
+/// for y in (0..3).step_by(2) {
+///     for x in 0..256 {
+///         let upper = img.get_pixel(x, y);
+///         write!(expected, "\x1B[38;2;{};{};{}m", upper[0], upper[1], upper[2]).unwrap();
+///         if y + 1 < 3 {
+///             let lower = img.get_pixel(x, y + 1);
+///             write!(expected, "\x1B[48;2;{};{};{}m", lower[0], lower[1], lower[2]).unwrap();
+///         }
+///         expected.push('\u{2580}');
+///     }
+///     expected.push_str("\x1B[0m\n");
+/// }
+///
+/// let mut out = Vec::new();
+/// write_ansi_truecolor(&mut out, &DynamicImage::ImageRgb8(img));
+/// assert_eq!(String::from_utf8(out).unwrap(), expected);
+/// # }
+/// ```
+pub fn write_ansi_truecolor<W: Write>(out: &mut W, img: &DynamicImage) {
+    let (width, height) = img.dimensions();
+    let term_h = height.div_ceil(2);
+    let pixels = RgbPixels::new(img);
+
+    // Up to 38 bytes per cell, built up by hand and written a line at a time, as write!() per cell is slow for big images
+    let mut line = Vec::with_capacity(width as usize * 38 + ANSI_RESET_ATTRIBUTES.len() + 1);
+    for y in 0..term_h {
+        let upper_y = y * 2;
+        let lower_y = upper_y + 1;
+
+        line.clear();
+        for x in 0..width {
+            push_sgr_colour(&mut line, b"\x1B[38;2;", pixels.get(x, upper_y));
+            if lower_y != height {
+                push_sgr_colour(&mut line, b"\x1B[48;2;", pixels.get(x, lower_y));
+            }
+            line.extend_from_slice("\u{2580}".as_bytes()); // ▀
+        }
+        line.extend_from_slice(ANSI_RESET_ATTRIBUTES.as_bytes());
+        line.push(b'\n');
+        out.write_all(&line).unwrap();
+    }
+}
+
+/// Append the specified SGR escape prefix, then the colour's channels, separated by `;`, then the terminating `m`.
+fn push_sgr_colour(buf: &mut Vec<u8>, prefix: &[u8], clr: Rgb<u8>) {
+    buf.extend_from_slice(prefix);
+    for (i, &c) in clr.0.iter().enumerate() {
+        if i != 0 {
+            buf.push(b';');
+        }
+        if c >= 100 {
+            buf.push(b'0' + c / 100);
+        }
+        if c >= 10 {
+            buf.push(b'0' + c / 10 % 10);
+        }
+        buf.push(b'0' + c % 10);
+    }
+    buf.push(b'm');
+}
+
+/// Display the specified image in the default console using ANSI 24-bit escape colour codes,
+/// like `write_ansi_truecolor()`, but with 2x2 pixels per cell.
+///
+/// Each cell gets the quadrant block and pair of colours that approximate its pixels best,
+/// so size the image with `subcell_resized_size()` for `(2, 2)` subcells.
+/// Cells along an odd last column or row repeat its pixels in their outer halves.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{DynamicImage, Rgb, RgbImage};
+/// # use imgcatr::ops::write_ansi_quadrant;
+/// # fn main() {
+/// let mut img = RgbImage::from_pixel(2, 2, Rgb([0x00, 0x00, 0x00]));
+/// img.put_pixel(1, 1, Rgb([0xFF, 0xFF, 0xFF]));
+///
+/// let mut out = Vec::new();
+/// write_ansi_quadrant(&mut out, &DynamicImage::ImageRgb8(img));
+/// assert_eq!(String::from_utf8(out).unwrap(), "\x1B[38;2;0;0;0m\x1B[48;2;255;255;255m\u{259B}\x1B[0m\n");
+///
+/// // The white last column and row get cells of their own
+/// let img = RgbImage::from_fn(3, 3, |x, y| if x == 2 || y == 2 { Rgb([0xFF, 0xFF, 0xFF]) } else { Rgb([0x00, 0x00, 0x00]) });
+/// let mut out = Vec::new();
+/// write_ansi_quadrant(&mut out, &DynamicImage::ImageRgb8(img));
+/// assert_eq!(String::from_utf8(out).unwrap(),
+///            "\x1B[38;2;0;0;0m\x1B[48;2;0;0;0m\u{2598}\x1B[38;2;255;255;255m\x1B[48;2;255;255;255m\u{2598}\x1B[0m\n\
+///             \x1B[38;2;255;255;255m\x1B[48;2;255;255;255m\u{2598}\x1B[38;2;255;255;255m\x1B[48;2;255;255;255m\u{2598}\x1B[0m\n");
+/// # }
+/// ```
+pub fn write_ansi_quadrant<W: Write>(out: &mut W, img: &DynamicImage) {
+    let (width, height) = img.dimensions();
+
+    for y in 0..height.div_ceil(2) {
+        let (upper_y, lower_y) = (y * 2, (y * 2 + 1).min(height - 1));
+        for x in 0..width.div_ceil(2) {
+            let (left_x, right_x) = (x * 2, (x * 2 + 1).min(width - 1));
+            let pixels = [img.get_pixel(left_x, upper_y).to_rgb(),
+                          img.get_pixel(right_x, upper_y).to_rgb(),
+                          img.get_pixel(left_x, lower_y).to_rgb(),
+                          img.get_pixel(right_x, lower_y).to_rgb()];
+            let (mask, fg, bg) = quadrant_split(&pixels);
+
+            write!(out,
+                   "\x1B[38;2;{};{};{}m\
+                    \x1B[48;2;{};{};{}m{}",
+                   fg[0],
+                   fg[1],
+                   fg[2],
+                   bg[0],
+                   bg[1],
+                   bg[2],
+                   QUADRANT_BLOCKS[mask])
+                .unwrap();
+        }
+        writeln!(out, "{}", ANSI_RESET_ATTRIBUTES).unwrap();
+    }
+}
+
+/// Find the split of a cell's quadrants into foreground ones, set in the returned mask, and background ones,
+/// and the colours therefor, that approximate its pixels with the least total `colour_distance()`.
+///
+/// The upper left quadrant is always in the foreground, as swapping the colours gives the complementary split.
+fn quadrant_split(pixels: &[Rgb<u8>; 4]) -> (usize, Rgb<u8>, Rgb<u8>) {
+    let mean = |mask: usize, set: bool| {
+        let mut sum = [0u32; 3];
+        let mut count = 0;
+        for (_, px) in pixels.iter().enumerate().filter(|&(i, _)| (mask & (1 << i) != 0) == set) {
+            for c in 0..3 {
+                sum[c] += px[c] as u32;
+            }
+            count += 1;
+        }
+        if count == 0 { None } else { Some(Rgb(sum.map(|v| ((v + count / 2) / count) as u8))) }
+    };
+
+    (1..QUADRANT_BLOCKS.len())
+        .step_by(2)
+        .map(|mask| {
+            let fg = mean(mask, true).unwrap();
+            let bg = mean(mask, false).unwrap_or(fg);
+            let error = pixels.iter()
+                .enumerate()
+                .map(|(i, px)| colour_distance(*px, if mask & (1 << i) != 0 { &fg } else { &bg }))
+                .sum::<f32>();
+            (error, mask, fg, bg)
+        })
+        .min_by(|lhs, rhs| lhs.0.partial_cmp(&rhs.0).unwrap())
+        .map(|(_, mask, fg, bg)| (mask, fg, bg))
+        .unwrap()
+}
+
+/// Display the specified image in the default console using ANSI 24-bit escape colour codes,
+/// like `write_ansi_truecolor()`, but with one pixel per cell, drawn as a space with its colour as background.
+///
+/// This avoids the gaps some fonts leave around `▀`; halve the height given by `image_resized_size()` for it.
+pub fn write_ansi_truecolor_bg_only<W: Write>(out: &mut W, img: &DynamicImage) {
+    let (width, height) = img.dimensions();
+
+    for y in 0..height {
+        for x in 0..width {
+            let pixel = img.get_pixel(x, y).to_rgb();
+            write!(out, "\x1B[48;2;{};{};{}m ", pixel[0], pixel[1], pixel[2]).unwrap();
+        }
+        writeln!(out, "{}", ANSI_RESET_ATTRIBUTES).unwrap();
+    }
+}
+
+/// Play the specified animation in place using ANSI 24-bit escape colour codes, like `write_ansi_truecolor()`,
+/// `loops` times over, or forever for `0`.
+///
+/// The frames are resized to the specified size with the specified filter up front. Before each frame but the first,
+/// the cursor is moved back up over the previous one to draw over it,
+/// then the frame is shown for its delay, but no more than `max_fps` frames are drawn a second.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{Delay, Frame, Rgba, RgbaImage};
+/// # use image::imageops::FilterType;
+/// # use imgcatr::ops::write_ansi_animated;
+/// # fn main() {
+/// let frame = |clr| Frame::from_parts(RgbaImage::from_pixel(2, 4, Rgba(clr)), 0, 0, Delay::from_numer_denom_ms(1, 1));
+/// let frames = [frame([0xFF, 0x00, 0x00, 0xFF]), frame([0x00, 0x00, 0xFF, 0xFF])];
+///
+/// let mut out = Vec::new();
+/// write_ansi_animated(&mut out, &frames, (1, 2), FilterType::Nearest, 2, 1000.0);
+/// let out = String::from_utf8(out).unwrap();
+/// assert!(out.starts_with("\x1B[38;2;255;0;0m"));
+/// assert_eq!(out.matches("\x1B[1A").count(), 3);
+/// assert!(out.ends_with("\x1B[1A\x1B[38;2;0;0;255m\x1B[48;2;0;0;255m\u{2580}\x1B[0m\n"));
+/// # }
+/// ```
+pub fn write_ansi_animated<W: Write>(out: &mut W, frames: &[Frame], size: (u32, u32), filter: FilterType, loops: u32, max_fps: f32) {
+    let frames = frames.iter()
+        .map(|frame| (resize_image(&DynamicImage::ImageRgba8(frame.buffer().clone()), size, filter), Duration::from(frame.delay())))
+        .collect::<Vec<_>>();
+
+    let mut limiter = FrameLimiter::new(max_fps);
+    let mut rows = 0;
+    let mut played = 0;
+    while loops == 0 || played < loops {
+        for (img, delay) in &frames {
+            limiter.wait();
+            if rows != 0 {
+                write!(out, "\x1B[{}A", rows).unwrap();
+            }
+            write_ansi_truecolor(out, img);
+            out.flush().unwrap();
+            rows = img.height().div_ceil(2);
+            thread::sleep(*delay);
+        }
+        played += 1;
+    }
+}
+
+/// Encode the specified animation as an animated GIF, with each frame resized to the specified size with the specified filter
+/// and kept up for its original delay, `loops` times over, or forever for `0`.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{AnimationDecoder, Delay, Frame, Rgba, RgbaImage};
+/// # use image::codecs::gif::GifDecoder;
+/// # use image::imageops::FilterType;
+/// # use imgcatr::ops::write_gif_animated;
+/// # fn main() {
+/// let frame = |clr, ms| Frame::from_parts(RgbaImage::from_pixel(4, 2, Rgba(clr)), 0, 0, Delay::from_numer_denom_ms(ms, 1));
+/// let frames = [frame([0xFF, 0x00, 0x00, 0xFF], 20), frame([0x00, 0xFF, 0x00, 0xFF], 40), frame([0x00, 0x00, 0xFF, 0xFF], 60)];
+///
+/// let mut out = Vec::new();
+/// write_gif_animated(&mut out, &frames, (2, 1), FilterType::Nearest, 0);
+/// let resized = GifDecoder::new(&out[..]).unwrap().into_frames().collect_frames().unwrap();
+/// assert_eq!(resized.len(), frames.len());
+/// for (resized, frame) in resized.iter().zip(&frames) {
+///     assert_eq!(resized.buffer().dimensions(), (2, 1));
+///     assert_eq!(resized.delay(), frame.delay());
+/// }
+/// assert_eq!(resized[2].buffer()[(1, 0)], Rgba([0x00, 0x00, 0xFF, 0xFF]));
+/// # }
+/// ```
+pub fn write_gif_animated<W: Write>(out: &mut W, frames: &[Frame], size: (u32, u32), filter: FilterType, loops: u32) {
+    let mut encoder = GifEncoder::new(out);
+    encoder.set_repeat(if loops == 0 {
+            Repeat::Infinite
+        } else {
+            Repeat::Finite(loops.saturating_sub(1).min(u16::MAX as u32) as u16)
+        })
+        .unwrap();
+    encoder.encode_frames(frames.iter().map(|frame| {
+            let resized = resize_image(&DynamicImage::ImageRgba8(frame.buffer().clone()), size, filter).into_rgba8();
+            Frame::from_parts(resized, 0, 0, frame.delay())
+        }))
+        .unwrap();
+}
+
+/// Display the specified image in the terminal with the Kitty graphics protocol, transmitting its actual RGBA pixels.
+///
+/// The base64-encoded pixels are split into chunks of at most `KITTY_CHUNK_SIZE` bytes, each in its own escape,
+/// all but the last flagged with `m=1`; the terminal is asked not to reply, and the cursor ends up on the line below.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{DynamicImage, Rgba, RgbaImage};
+/// # use imgcatr::ops::write_kitty;
+/// # fn main() {
+/// let mut out = Vec::new();
+/// write_kitty(&mut out, &DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 1, Rgba([0xFF, 0x00, 0x00, 0xFF]))));
+/// assert_eq!(out, b"\x1B_Ga=T,f=32,s=1,v=1,q=2,m=0;/wAA/w==\x1B\\\n");
+///
+/// let mut out = Vec::new();
+/// write_kitty(&mut out, &DynamicImage::new_rgba8(40, 40));
+/// let out = String::from_utf8(out).unwrap();
+/// assert_eq!(out.matches("\x1B_G").count(), 3);
+/// assert!(out.starts_with("\x1B_Ga=T,f=32,s=40,v=40,q=2,m=1;"));
+/// assert!(out.contains("\x1B\\\x1B_Gm=1;"));
+/// assert!(out.contains("\x1B\\\x1B_Gm=0;"));
+/// # }
+/// ```
+pub fn write_kitty<W: Write>(out: &mut W, img: &DynamicImage) {
+    let (width, height) = img.dimensions();
+    let payload = base64_encode(img.to_rgba8().as_raw());
+
+    let mut chunks = payload.as_bytes().chunks(KITTY_CHUNK_SIZE).peekable();
+    let mut first = true;
+    while let Some(chunk) = chunks.next() {
+        let more = chunks.peek().is_some() as u8;
+        if first {
+            write!(out, "\x1B_Ga=T,f=32,s={},v={},q=2,m={};", width, height, more).unwrap();
+            first = false;
+        } else {
+            write!(out, "\x1B_Gm={};", more).unwrap();
+        }
+        out.write_all(chunk).unwrap();
+        write!(out, "\x1B\\").unwrap();
+    }
+    writeln!(out).unwrap();
+}
+
+/// Create or truncate the specified file to write rendered output to.
+///
+/// Opening a FIFO blocks until something reads from it, so that's announced on stderr first rather than seeming to hang.
+/// With a timeout, it's instead opened without blocking, retrying until something reads from it,
+/// and failing with `Error::OpeningOutputFailed` if nothing does in time.
+///
+/// # Examples
+///
+/// ```
+/// # use imgcatr::ops::create_output;
+/// # use imgcatr::Error;
+/// # use std::{env, fs};
+/// # use std::io::Write;
+/// # use std::path::Path;
+/// let path = env::temp_dir().join("imgcatr-output.txt");
+/// writeln!(create_output(&path, None).unwrap(), "art").unwrap();
+/// assert_eq!(fs::read_to_string(&path).unwrap(), "art\n");
+/// # fs::remove_file(&path).unwrap();
+///
+/// assert_eq!(create_output(Path::new("nonexistent/art.txt"), None).unwrap_err(),
+///            Error::OpeningOutputFailed("nonexistent/art.txt".to_string()));
+/// ```
+pub fn create_output(path: &Path, timeout: Option<Duration>) -> Result<File, Error> {
+    let failed = || Error::OpeningOutputFailed(path.display().to_string());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{FileTypeExt, OpenOptionsExt};
+        use std::os::unix::io::AsRawFd;
+        use std::time::Instant;
+
+        if std::fs::metadata(path).map(|meta| meta.file_type().is_fifo()).unwrap_or(false) {
+            let timeout = match timeout {
+                Some(timeout) => timeout,
+                None => {
+                    eprintln!("\"{}\" is a FIFO, waiting for something to read from it", path.display());
+                    return File::create(path).map_err(|_| failed());
+                }
+            };
+
+            eprintln!("\"{}\" is a FIFO, waiting up to {:.1}s for something to read from it", path.display(), timeout.as_secs_f32());
+            let start = Instant::now();
+            loop {
+                match std::fs::OpenOptions::new().write(true).custom_flags(libc::O_NONBLOCK).open(path) {
+                    Ok(file) => {
+                        // Only the open shouldn't block, writes should wait for the reader
+                        let fd = file.as_raw_fd();
+                        unsafe { libc::fcntl(fd, libc::F_SETFL, libc::fcntl(fd, libc::F_GETFL) & !libc::O_NONBLOCK) };
+                        return Ok(file);
+                    }
+                    // No reader yet
+                    Err(err) if err.raw_os_error() == Some(libc::ENXIO) && start.elapsed() < timeout => thread::sleep(Duration::from_millis(50)),
+                    Err(_) => return Err(failed()),
+                }
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = timeout;
+
+    File::create(path).map_err(|_| failed())
+}
+
+/// Copy the specified rendered output to the terminal's clipboard with an OSC 52 escape, and return how much of it fit.
+///
+/// Output that would exceed `OSC52_MAX_PAYLOAD` once encoded is cut after the last whole line that fits.
+///
+/// # Examples
+///
+/// ```
+/// # use imgcatr::ops::write_clipboard;
+/// let mut out = Vec::new();
+/// assert_eq!(write_clipboard(&mut out, b"foo\n"), 4);
+/// assert_eq!(out, b"\x1B]52;c;Zm9vCg==\x07");
+/// ```
+pub fn write_clipboard<W: Write>(out: &mut W, rendered: &[u8]) -> usize {
+    let max_len = OSC52_MAX_PAYLOAD / 4 * 3;
+    let len = if rendered.len() <= max_len {
+        rendered.len()
+    } else {
+        rendered[..max_len].iter().rposition(|&b| b == b'\n').map(|i| i + 1).unwrap_or(0)
+    };
+
+    write!(out, "\x1B]52;c;{}\x07", base64_encode(&rendered[..len])).unwrap();
+    len
+}
+
+/// Display the specified image in the ascii art style, one glyph per pixel.
+///
+/// Cells being about twice as tall as they're wide, size the image with `subcell_resized_size()` for `(1, 1)` subcells,
+/// which halves its height, to keep its proportions.
+///
+/// Each pixel's intensity is spread over the ramp, its first glyph being for the least intense pixels, like `ASCII_RAMP`.
+/// With `invert`, dark pixels get the dense glyphs instead of bright ones, for dark-on-light terminals.
+/// With `linear`, brightness is taken as `luminance_linear()` instead of the plain average of the channels.
+/// Fully transparent pixels get the first glyph; images without alpha are opaque throughout.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{DynamicImage, Rgba, RgbaImage};
+/// # use imgcatr::ops::write_ascii;
+/// # use imgcatr::util::ASCII_RAMP;
+/// # fn main() {
+/// // A grey gradient over the top row, transparent below.
+/// let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(4, 2, |x, y| {
+///     let v = x as u8 * 0x55;
+///     Rgba([v, v, v, if y < 1 { 0xFF } else { 0x00 }])
+/// }));
+/// let mut out = Vec::new();
+/// write_ascii(&mut out, &img, &ASCII_RAMP, false, false);
+/// assert_eq!(out, b" ,+@\n    \n");
+///
+/// out.clear();
+/// write_ascii(&mut out, &img, &ASCII_RAMP, true, false);
+/// assert_eq!(out, b"@+, \n    \n");
+/// # }
+/// ```
+pub fn write_ascii<W: Write>(out: &mut W, img: &DynamicImage, ramp: &[char], invert: bool, linear: bool) {
+    let (width, height) = img.dimensions();
+    for y in 0..height {
+        for x in 0..width {
+            let pix = img.get_pixel(x, y).to_rgba();
+            let mut intent = if linear {
+                luminance_linear(pix.to_rgb())
+            } else {
+                pix[0] / 3 + pix[1] / 3 + pix[2] / 3
+            };
+            if pix[3] == 0 {
+                intent = 0;
+            } else if invert {
+                intent = 255 - intent;
+            }
+            write!(out, "{}", ramp[intent as usize * ramp.len() / 256]).unwrap();
+        }
+        writeln!(out).unwrap();
+    }
+}
+
+/// Display the specified image as braille dots, 2x4 per cell, each drawn if its pixel's `luminance()` is at least `threshold`.
+///
+/// With `invert`, dots are drawn for the pixels below the threshold instead, for dark-on-light terminals.
+/// Fully transparent pixels never get a dot. Size the image with `subcell_resized_size()` for `(2, 4)` subcells;
+/// cells hanging over the image's right or bottom edge get no dots past it.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{DynamicImage, Luma, GrayImage};
+/// # use imgcatr::ops::write_braille;
+/// # fn main() {
+/// // Only the left column is bright.
+/// let img = DynamicImage::ImageLuma8(GrayImage::from_fn(2, 4, |x, _| Luma([if x == 0 { 0xFF } else { 0x00 }])));
+///
+/// let mut out = Vec::new();
+/// write_braille(&mut out, &img, 128, false);
+/// assert_eq!(String::from_utf8(out).unwrap(), "\u{2847}\n");
+///
+/// let mut out = Vec::new();
+/// write_braille(&mut out, &img, 128, true);
+/// assert_eq!(String::from_utf8(out).unwrap(), "\u{28B8}\n");
+///
+/// // All bright, with a column and row of cells for the leftover pixels
+/// let img = DynamicImage::ImageLuma8(GrayImage::from_pixel(3, 5, Luma([0xFF])));
+/// let mut out = Vec::new();
+/// write_braille(&mut out, &img, 128, false);
+/// assert_eq!(String::from_utf8(out).unwrap(), "\u{28FF}\u{2847}\n\u{2809}\u{2801}\n");
+/// # }
+/// ```
+pub fn write_braille<W: Write>(out: &mut W, img: &DynamicImage, threshold: u8, invert: bool) {
+    let (width, height) = img.dimensions();
+
+    for y in 0..height.div_ceil(4) {
+        for x in 0..width.div_ceil(2) {
+            let mut bits = 0;
+            for (dy, row) in BRAILLE_DOTS.iter().enumerate().filter(|&(dy, _)| y * 4 + (dy as u32) < height) {
+                for (dx, &bit) in row.iter().enumerate().filter(|&(dx, _)| x * 2 + (dx as u32) < width) {
+                    let pix = img.get_pixel(x * 2 + dx as u32, y * 4 + dy as u32).to_rgba();
+                    if pix[3] != 0 && (luminance(pix.to_rgb()) >= threshold) != invert {
+                        bits |= bit;
+                    }
+                }
+            }
+            write!(out, "{}", char::from_u32(0x2800 + bits as u32).unwrap()).unwrap();
+        }
+        writeln!(out).unwrap();
+    }
+}
@@ -1,4 +1,5 @@
 pub use image::DynamicImage;
+use std::io::Write;
 #[cfg(target_os = "windows")]
 use self::imports::*;
 
@@ -15,11 +16,14 @@ mod imports {
 
 
 /// Display the specified image in the default console using WinAPI.
+///
+/// The half-blocks are written to `out`, which is flushed before they're coloured in, so it should be the console.
 #[cfg(target_os = "windows")]
-pub fn write_no_ansi(img: &DynamicImage) {
+pub fn write_no_ansi<W: Write>(out: &mut W, img: &DynamicImage) {
     let (width, height) = img.dimensions();
-    let term_h = height / 2;
-    print!("{}", mul_str(&format!("{}\n", mul_str("\u{2580}", width as usize)), term_h as usize)); // ▀
+    let term_h = height.div_ceil(2);
+    write!(out, "{}", mul_str(&format!("{}\n", mul_str("\u{2580}", width as usize)), term_h as usize)).unwrap(); // ▀
+    out.flush().unwrap();
 
     let console_h = unsafe { GetStdHandle(STD_OUTPUT_HANDLE) };
     let mut console_info = CONSOLE_SCREEN_BUFFER_INFOEX {
@@ -62,4 +66,4 @@ pub fn write_no_ansi(img: &DynamicImage) {
 ///
 /// Or, actually, don't. This is Linux, after all...
 #[cfg(not(target_os = "windows"))]
-pub fn write_no_ansi(_: &DynamicImage) {}
+pub fn write_no_ansi<W: Write>(_: &mut W, _: &DynamicImage) {}
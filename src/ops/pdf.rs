@@ -0,0 +1,44 @@
+use self::super::super::Error;
+use image::DynamicImage;
+use std::path::PathBuf;
+
+#[cfg(feature = "pdf")]
+use pdfium_render::prelude::{Pdfium, PdfRenderConfig};
+
+
+/// Render the specified page, counting from 0, of the specified PDF file into an image,
+/// or the last one, saying so on stderr, if it doesn't have that many, like `--frame` does for animations.
+///
+/// With a size, the page is rendered to fit within it, otherwise at 72 DPI, so that resizing it afterwards loses the least detail.
+///
+/// The [Pdfium](https://pdfium.googlesource.com/pdfium) library is loaded at runtime, from the system's libraries or the current directory.
+#[cfg(feature = "pdf")]
+pub fn load_pdf_page(file: &(String, PathBuf), page: usize, size: Option<(u32, u32)>) -> Result<DynamicImage, Error> {
+    let failed = |_| Error::RenderingPdfFailed(file.0.clone());
+
+    let bindings = Pdfium::bind_to_system_library()
+        .or_else(|_| Pdfium::bind_to_library(Pdfium::pdfium_platform_library_name_at_path("./")))
+        .map_err(failed)?;
+    let pdfium = Pdfium::new(bindings);
+    let document = pdfium.load_pdf_from_file(&file.1, None).map_err(failed)?;
+    let pages = document.pages().len() as usize;
+    if page >= pages && pages != 0 {
+        eprintln!("\"{}\" only has {} pages, displaying the last one", file.0, pages);
+    }
+    let page = document.pages().get(page.min(pages.saturating_sub(1)) as u16).map_err(failed)?;
+
+    let mut config = PdfRenderConfig::new();
+    if let Some((width, height)) = size {
+        config = config.set_target_width(width as i32).set_maximum_height(height as i32);
+    }
+    let img = page.render_with_config(&config).map_err(failed)?.as_image();
+    Ok(img)
+}
+
+/// Render the specified page of the specified PDF file into an image.
+///
+/// Or, actually, don't. This build doesn't have the `pdf` feature.
+#[cfg(not(feature = "pdf"))]
+pub fn load_pdf_page(file: &(String, PathBuf), _: usize, _: Option<(u32, u32)>) -> Result<DynamicImage, Error> {
+    Err(Error::PdfUnsupported(file.0.clone()))
+}
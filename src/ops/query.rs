@@ -0,0 +1,68 @@
+use self::super::super::util::{BACKGROUND_COLOUR_QUERY, parse_background_colour};
+use image::Rgb;
+
+
+/// Ask the terminal for its background colour with an OSC 11 query, waiting a little for the reply.
+///
+/// Gives up, returning `None`, if either standard stream isn't a terminal or it doesn't reply in time,
+/// as is the case for terminals not supporting the query.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use imgcatr::ops::query_terminal_background;
+/// # use imgcatr::Theme;
+/// let theme = query_terminal_background().map(Theme::from_background).unwrap_or(Theme::Dark);
+/// ```
+pub fn query_terminal_background() -> Option<Rgb<u8>> {
+    query_terminal(BACKGROUND_COLOUR_QUERY.as_bytes(), b"\x07\\").and_then(|reply| parse_background_colour(&reply))
+}
+
+/// Write the specified query to the controlling terminal and read its reply, up to and including any of `terminators`.
+///
+/// Gives up, returning `None`, if either standard stream isn't a terminal or no full reply comes within a short timeout.
+#[cfg(unix)]
+pub(crate) fn query_terminal(query: &[u8], terminators: &[u8]) -> Option<Vec<u8>> {
+    use std::io::{IsTerminal, Read, Write, stdin, stdout};
+    use std::os::unix::io::AsRawFd;
+    use std::fs::OpenOptions;
+
+    if !stdin().is_terminal() || !stdout().is_terminal() {
+        return None;
+    }
+
+    let mut tty = OpenOptions::new().read(true).write(true).open("/dev/tty").ok()?;
+    let fd = tty.as_raw_fd();
+    let mut original: libc::termios = unsafe { std::mem::zeroed() };
+    if unsafe { libc::tcgetattr(fd, &mut original) } != 0 {
+        return None;
+    }
+    let mut raw = original;
+    raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+    raw.c_cc[libc::VMIN] = 0;
+    raw.c_cc[libc::VTIME] = 0;
+    if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &raw) } != 0 {
+        return None;
+    }
+
+    let terminated = |reply: &[u8]| reply.iter().any(|b| terminators.contains(b));
+    let mut reply = Vec::new();
+    if tty.write_all(query).and_then(|_| tty.flush()).is_ok() {
+        let mut buf = [0; 64];
+        let mut poll = libc::pollfd { fd, events: libc::POLLIN, revents: 0 };
+        while reply.len() < 256 && !terminated(&reply) && unsafe { libc::poll(&mut poll, 1, 100) } > 0 {
+            match tty.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(read) => reply.extend_from_slice(&buf[..read]),
+            }
+        }
+    }
+
+    unsafe { libc::tcsetattr(fd, libc::TCSANOW, &original) };
+    Some(reply).filter(|r| terminated(r))
+}
+
+#[cfg(not(unix))]
+pub(crate) fn query_terminal(_: &[u8], _: &[u8]) -> Option<Vec<u8>> {
+    None
+}
@@ -0,0 +1,48 @@
+use self::super::super::Error;
+use image::ImageFormat;
+use std::path::Path;
+
+#[cfg(feature = "remote")]
+use std::io::Read;
+#[cfg(feature = "remote")]
+use std::time::Duration;
+
+
+/// Check whether the specified image path is actually an HTTP(S) URL, to be fetched with `fetch_image()`.
+///
+/// # Examples
+///
+/// ```
+/// # use std::path::Path;
+/// # use imgcatr::ops::is_url;
+/// assert!(is_url(Path::new("https://example.com/pic.png")));
+/// assert!(is_url(Path::new("http://example.com/pic.png")));
+/// assert!(!is_url(Path::new("pic.png")));
+/// assert!(!is_url(Path::new("ftp://example.com/pic.png")));
+/// ```
+pub fn is_url(path: &Path) -> bool {
+    path.to_str().is_some_and(|path| path.starts_with("http://") || path.starts_with("https://"))
+}
+
+/// Download the image at the specified URL, and get its bytes and the format its `Content-Type` names, if any.
+///
+/// Guess the format with `guess_format_from_memory()` if the server doesn't name one,
+/// then decode it with `load_image_from_reader()`.
+#[cfg(feature = "remote")]
+pub fn fetch_image(url: &str) -> Result<(Vec<u8>, Option<ImageFormat>), Error> {
+    // ureq's errors already start with the URL
+    let response = ureq::AgentBuilder::new().timeout(Duration::from_secs(30)).build().get(url).call().map_err(|err| Error::FetchFailed(err.to_string()))?;
+    let format = response.header("Content-Type").and_then(|mime| ImageFormat::from_mime_type(mime.split(';').next().unwrap_or_default().trim()));
+
+    let mut buf = Vec::new();
+    response.into_reader().read_to_end(&mut buf).map_err(|err| Error::FetchFailed(format!("{}: {}", url, err)))?;
+    Ok((buf, format))
+}
+
+/// Download the image at the specified URL, and get its bytes and the format its `Content-Type` names, if any.
+///
+/// Or, actually, don't. This build doesn't have the `remote` feature.
+#[cfg(not(feature = "remote"))]
+pub fn fetch_image(url: &str) -> Result<(Vec<u8>, Option<ImageFormat>), Error> {
+    Err(Error::FetchFailed(format!("{}: built without the remote feature", url)))
+}
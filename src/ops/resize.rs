@@ -0,0 +1,85 @@
+use std::time::Duration;
+use std::sync::Once;
+use std::thread;
+
+#[cfg(unix)]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+
+/// How often `ResizeWatcher::wait()` checks for a resize.
+static POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[cfg(unix)]
+static RESIZED: AtomicBool = AtomicBool::new(false);
+
+static INSTALL_HANDLER: Once = Once::new();
+
+
+/// Watcher for the terminal being resized, through `SIGWINCH` on Unix and by polling the terminal size elsewhere.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use imgcatr::ops::ResizeWatcher;
+/// let mut watcher = ResizeWatcher::new();
+/// loop {
+///     let size = watcher.wait();
+///     // Redraw for the new size…
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ResizeWatcher {
+    last_size: Option<(usize, usize)>,
+}
+
+impl ResizeWatcher {
+    /// Start watching for resizes, installing the `SIGWINCH` handler if that hasn't happened yet.
+    pub fn new() -> ResizeWatcher {
+        INSTALL_HANDLER.call_once(install_handler);
+        ResizeWatcher { last_size: term_size::dimensions() }
+    }
+
+    /// Block until the terminal is resized, then get its new size in cells, if it can still be detected.
+    pub fn wait(&mut self) -> Option<(usize, usize)> {
+        loop {
+            thread::sleep(POLL_INTERVAL);
+
+            let size = term_size::dimensions();
+            if take_resized() || size != self.last_size {
+                self.last_size = size;
+                return size;
+            }
+        }
+    }
+}
+
+impl Default for ResizeWatcher {
+    fn default() -> ResizeWatcher {
+        ResizeWatcher::new()
+    }
+}
+
+
+#[cfg(unix)]
+fn install_handler() {
+    unsafe { libc::signal(libc::SIGWINCH, handle_winch as *const () as libc::sighandler_t) };
+}
+
+/// Only set a flag, which is async-signal-safe, for `wait()` to pick up.
+#[cfg(unix)]
+extern "C" fn handle_winch(_: libc::c_int) {
+    RESIZED.store(true, Ordering::SeqCst);
+}
+
+#[cfg(unix)]
+fn take_resized() -> bool {
+    RESIZED.swap(false, Ordering::SeqCst)
+}
+
+#[cfg(not(unix))]
+fn install_handler() {}
+
+#[cfg(not(unix))]
+fn take_resized() -> bool {
+    false
+}
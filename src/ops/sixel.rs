@@ -0,0 +1,137 @@
+use image::{GenericImageView, DynamicImage, Rgb};
+use color_quant::NeuQuant;
+use std::collections::HashMap;
+use std::io::Write;
+
+
+/// Most colours a sixel palette is assumed to hold, which is what most terminals support.
+static SIXEL_MAX_COLOURS: usize = 256;
+
+/// Alpha below which a pixel is left undrawn, showing the terminal's background.
+static SIXEL_ALPHA_THRESHOLD: u8 = 0x80;
+
+
+/// Display the specified image in the terminal as sixel graphics, with actual pixels instead of half-blocks.
+///
+/// Images with more than 256 colours are quantised to an adaptive palette of that many,
+/// and (mostly) transparent pixels aren't drawn at all.
+/// Size the image with `pixel_resized_size()`, as sixels aren't packed two to a cell.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use image::{DynamicImage, Rgba, RgbaImage};
+/// # use imgcatr::ops::write_sixel;
+/// # fn main() {
+/// let mut img = RgbaImage::from_pixel(6, 7, Rgba([0xFF, 0x00, 0x00, 0xFF]));
+/// img.put_pixel(1, 0, Rgba([0x00, 0x00, 0xFF, 0xFF]));
+/// img.put_pixel(1, 1, Rgba([0x00, 0x00, 0x00, 0x00]));
+///
+/// let mut out = Vec::new();
+/// write_sixel(&mut out, &DynamicImage::ImageRgba8(img));
+/// assert_eq!(String::from_utf8(out).unwrap(),
+///            "\x1BP0;1q\"1;1;6;7#0;2;100;0;0#1;2;0;0;100\
+///             #0~{!4~$#1?@-\
+///             #0!6@\x1B\\");
+/// # }
+/// ```
+pub fn write_sixel<W: Write>(out: &mut W, img: &DynamicImage) {
+    let (width, height) = img.dimensions();
+    let (palette, indices) = quantise(img);
+
+    write!(out, "\x1BP0;1q\"1;1;{};{}", width, height).unwrap();
+    for (i, clr) in palette.iter().enumerate() {
+        write!(out,
+               "#{};2;{};{};{}",
+               i,
+               (clr[0] as u32 * 100 + 127) / 255,
+               (clr[1] as u32 * 100 + 127) / 255,
+               (clr[2] as u32 * 100 + 127) / 255)
+            .unwrap();
+    }
+
+    let mut bits = vec![0u8; palette.len() * width as usize];
+    for band_y in (0..height).step_by(6) {
+        bits.iter_mut().for_each(|b| *b = 0);
+        let mut used = vec![false; palette.len()];
+        for dy in 0..6.min(height - band_y) {
+            for x in 0..width {
+                if let Some(idx) = indices[((band_y + dy) * width + x) as usize] {
+                    bits[idx as usize * width as usize + x as usize] |= 1 << dy;
+                    used[idx as usize] = true;
+                }
+            }
+        }
+
+        if band_y != 0 {
+            write!(out, "-").unwrap();
+        }
+        let mut first = true;
+        for (i, row) in bits.chunks(width as usize).enumerate().filter(|&(i, _)| used[i]) {
+            if !first {
+                write!(out, "$").unwrap();
+            }
+            first = false;
+            write!(out, "#{}", i).unwrap();
+            write_sixel_run_lengths(out, row);
+        }
+    }
+    write!(out, "\x1B\\").unwrap();
+}
+
+/// Write a row of sixels, trimming trailing blanks and run-length encoding repeats.
+fn write_sixel_run_lengths<W: Write>(out: &mut W, row: &[u8]) {
+    let len = row.iter().rposition(|&b| b != 0).map_or(0, |last| last + 1);
+    let mut x = 0;
+    while x < len {
+        let run = row[x..len].iter().take_while(|&&b| b == row[x]).count();
+        let sixel = (0x3F + row[x]) as char;
+        if run > 3 {
+            write!(out, "!{}{}", run, sixel).unwrap();
+        } else {
+            for _ in 0..run {
+                write!(out, "{}", sixel).unwrap();
+            }
+        }
+        x += run;
+    }
+}
+
+/// Get the image's palette and each pixel's index into it, `None` for transparent ones.
+///
+/// The palette holds the image's colours in order of appearance if there are few enough, or NeuQuant's approximations otherwise.
+fn quantise(img: &DynamicImage) -> (Vec<Rgb<u8>>, Vec<Option<u8>>) {
+    let img = img.to_rgba8();
+
+    let mut exact = HashMap::new();
+    let mut palette = Vec::new();
+    let mut indices = Vec::with_capacity(img.len() / 4);
+    for px in img.pixels() {
+        if px[3] < SIXEL_ALPHA_THRESHOLD {
+            indices.push(None);
+            continue;
+        }
+        let clr = Rgb([px[0], px[1], px[2]]);
+        let idx = *exact.entry(clr).or_insert_with(|| {
+            palette.push(clr);
+            palette.len() - 1
+        });
+        if palette.len() > SIXEL_MAX_COLOURS {
+            break;
+        }
+        indices.push(Some(idx as u8));
+    }
+    if palette.len() <= SIXEL_MAX_COLOURS {
+        return (palette, indices);
+    }
+
+    let opaque = img.pixels().filter(|px| px[3] >= SIXEL_ALPHA_THRESHOLD).flat_map(|px| [px[0], px[1], px[2], 0xFF]).collect::<Vec<_>>();
+    let quant = NeuQuant::new(10, SIXEL_MAX_COLOURS, &opaque);
+    let palette = quant.color_map_rgb().chunks(3).map(|c| Rgb([c[0], c[1], c[2]])).collect();
+    let indices = img.pixels()
+        .map(|px| if px[3] < SIXEL_ALPHA_THRESHOLD { None } else { Some(quant.index_of(&[px[0], px[1], px[2], 0xFF]) as u8) })
+        .collect();
+    (palette, indices)
+}
@@ -0,0 +1,44 @@
+use std::time::{Duration, Instant};
+use std::thread;
+
+
+/// Limiter keeping redraws to at most a given rate by sleeping off the rest of each frame's time slot.
+///
+/// # Examples
+///
+/// ```
+/// # use imgcatr::ops::FrameLimiter;
+/// # use std::time::{Duration, Instant};
+/// let mut limiter = FrameLimiter::new(100.0);
+/// let start = Instant::now();
+/// for _ in 0..3 {
+///     limiter.wait();
+///     // Redraw…
+/// }
+/// assert!(start.elapsed() >= Duration::from_millis(20));
+/// ```
+#[derive(Debug)]
+pub struct FrameLimiter {
+    interval: Duration,
+    last_frame: Option<Instant>,
+}
+
+impl FrameLimiter {
+    /// Limit to at most the specified amount of frames per second.
+    pub fn new(max_fps: f32) -> FrameLimiter {
+        FrameLimiter {
+            interval: Duration::from_secs_f32(1.0 / max_fps),
+            last_frame: None,
+        }
+    }
+
+    /// Sleep until the next frame may be drawn, which is right away for the first one.
+    pub fn wait(&mut self) {
+        if let Some(last_frame) = self.last_frame {
+            if let Some(left) = self.interval.checked_sub(last_frame.elapsed()) {
+                thread::sleep(left);
+            }
+        }
+        self.last_frame = Some(Instant::now());
+    }
+}
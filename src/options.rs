@@ -5,125 +5,1557 @@
 //! # Examples
 //!
 //! ```no_run
-//! # use termimage::Options;
+//! # use imgcatr::Options;
 //! let options = Options::parse();
-//! println!("Image to display: {}", options.image.0);
+//! println!("First image to display: {}", options.images[0].0);
+//! ```
+//!
+//! Or, without going through the command line:
+//!
+//! ```
+//! # use imgcatr::{AnsiOutputFormat, Options};
+//! let options = Options::builder().size((80, 24)).ansi(AnsiOutputFormat::ASCII).build().unwrap();
+//! assert_eq!(options.ansi_out, Some(AnsiOutputFormat::ASCII));
 //! ```
 
-use clap::{command, Arg};
-use std::path::PathBuf;
+use self::super::Error;
+use self::super::ops::{decodable_formats, guess_format, is_url, query_terminal, query_terminal_background};
+use self::super::util::{ANSI_COLOURS_BLACK_BG, ANSI_COLOURS_WHITE_BG, ASCII_RAMP, SIXEL_MAX_GEOMETRY_QUERY, default_output_size, luminance, parse_palette, parse_sixel_geometry};
+use clap::builder::{OsStringValueParser, TypedValueParser};
+use clap::{command, Arg, ArgMatches, Command};
+use std::path::{Path, PathBuf};
+use image::imageops::FilterType;
+use image::{ImageFormat, Rgb};
 // use std::str::FromStr;
-use term_size;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::process::exit;
+use std::io::stderr;
+use std::env;
 use std::fs;
+use std::ffi::OsString;
+
+
+/// Names of the ANSI output formats accepted by `--ansi`, `"simple"` meaning the one suited for the theme.
+static ANSI_FORMAT_NAMES: [&str; 11] = ["auto", "truecolor", "quadrant", "sixel", "kitty", "256", "simple", "simple-black", "simple-white", "braille", "ascii"];
 
 
 /// Supported ANSI output formats
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum AnsiOutputFormat {
+    /// The best of truecolor, 256-colour, and simple output the terminal supports, see `AnsiOutputFormat::resolve()`
+    Auto,
     /// Truecolor ANSI 24-bit colour
     Truecolor,
+    /// Truecolor ANSI 24-bit colour, with 2x2 pixels per cell out of quadrant blocks
+    Quadrant,
+    /// Sixel graphics, with actual pixels in up to 256 colours
+    Sixel,
+    /// Kitty graphics protocol, with actual pixels in 32-bit RGBA
+    Kitty,
+    /// ANSI 8-bit colour, out of the xterm 256-colour palette
+    Ansi256,
     /// Dumb ANSI 3-bit colour, for black backgrounds
     SimpleBlack,
     /// Dumb ANSI 3-bit colour, for white backgrounds
     SimpleWhite,
+    /// Monochrome braille dots, with 2x4 pixels per cell
+    Braille,
     /// ASCII Art
     ASCII,
 }
 
+impl AnsiOutputFormat {
+    /// Get the `--ansi` value selecting this format.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use imgcatr::AnsiOutputFormat;
+    /// assert_eq!(AnsiOutputFormat::Truecolor.name(), "truecolor");
+    /// assert_eq!(AnsiOutputFormat::SimpleWhite.name(), "simple-white");
+    /// ```
+    pub fn name(self) -> &'static str {
+        match self {
+            AnsiOutputFormat::Auto => "auto",
+            AnsiOutputFormat::Truecolor => "truecolor",
+            AnsiOutputFormat::Quadrant => "quadrant",
+            AnsiOutputFormat::Sixel => "sixel",
+            AnsiOutputFormat::Kitty => "kitty",
+            AnsiOutputFormat::Ansi256 => "256",
+            AnsiOutputFormat::SimpleBlack => "simple-black",
+            AnsiOutputFormat::SimpleWhite => "simple-white",
+            AnsiOutputFormat::Braille => "braille",
+            AnsiOutputFormat::ASCII => "ascii",
+        }
+    }
+
+    /// Get the format selected by the specified `--ansi` value, `"simple"` resolving to the theme's simple format.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use imgcatr::{AnsiOutputFormat, Theme};
+    /// assert_eq!(AnsiOutputFormat::from_name("ascii", Theme::Dark), Some(AnsiOutputFormat::ASCII));
+    /// assert_eq!(AnsiOutputFormat::from_name("simple", Theme::Light), Some(AnsiOutputFormat::SimpleWhite));
+    /// assert_eq!(AnsiOutputFormat::from_name("sepia", Theme::Dark), None);
+    /// ```
+    pub fn from_name(name: &str, theme: Theme) -> Option<AnsiOutputFormat> {
+        match name {
+            "auto" => Some(AnsiOutputFormat::Auto),
+            "truecolor" => Some(AnsiOutputFormat::Truecolor),
+            "quadrant" => Some(AnsiOutputFormat::Quadrant),
+            "sixel" => Some(AnsiOutputFormat::Sixel),
+            "kitty" => Some(AnsiOutputFormat::Kitty),
+            "256" => Some(AnsiOutputFormat::Ansi256),
+            "simple" => Some(theme.simple_format()),
+            "simple-black" => Some(AnsiOutputFormat::SimpleBlack),
+            "simple-white" => Some(AnsiOutputFormat::SimpleWhite),
+            "braille" => Some(AnsiOutputFormat::Braille),
+            "ascii" => Some(AnsiOutputFormat::ASCII),
+            _ => None,
+        }
+    }
+
+    /// Get the amount of distinct colours this format can output.
+    pub fn colour_count(self) -> u32 {
+        match self {
+            AnsiOutputFormat::Auto | AnsiOutputFormat::Truecolor | AnsiOutputFormat::Quadrant | AnsiOutputFormat::Kitty => 1 << 24,
+            AnsiOutputFormat::Sixel | AnsiOutputFormat::Ansi256 => 256,
+            AnsiOutputFormat::SimpleBlack | AnsiOutputFormat::SimpleWhite => 16,
+            AnsiOutputFormat::Braille | AnsiOutputFormat::ASCII => 0,
+        }
+    }
+
+    /// Get how many pixels across and down this format draws in each cell, to size the image with `subcell_resized_size()`.
+    ///
+    /// Sixel and Kitty graphics draw actual pixels, so size for them with `pixel_resized_size()` instead;
+    /// they, like `Auto`, get the half-block `(1, 2)`.
+    ///
+    /// # Examples
+    ///
+    /// A square image comes out about twice as many glyphs wide as it's lines tall, i.e. square, as ASCII art:
+    ///
+    /// ```
+    /// # extern crate image;
+    /// # extern crate imgcatr;
+    /// # use image::{DynamicImage, RgbImage};
+    /// # use image::imageops::FilterType;
+    /// # use imgcatr::ops::{resize_image, subcell_resized_size, write_ascii};
+    /// # use imgcatr::util::ASCII_RAMP;
+    /// # use imgcatr::{AnsiOutputFormat, ResizeMode};
+    /// # fn main() {
+    /// let img = DynamicImage::ImageRgb8(RgbImage::new(100, 100));
+    /// let (size, _) = subcell_resized_size((100, 100), (80, 24), AnsiOutputFormat::ASCII.subcells(), 2.0, ResizeMode::Fit);
+    ///
+    /// let mut out = Vec::new();
+    /// write_ascii(&mut out, &resize_image(&img, size, FilterType::Nearest), &ASCII_RAMP, false, false);
+    /// let lines = String::from_utf8(out).unwrap().lines().map(|l| l.chars().count()).collect::<Vec<_>>();
+    /// assert_eq!(lines.len(), 24);
+    /// assert!(lines.iter().all(|&l| l == 48));
+    /// # }
+    /// ```
+    pub fn subcells(self) -> (u32, u32) {
+        match self {
+            AnsiOutputFormat::Quadrant => (2, 2),
+            AnsiOutputFormat::Braille => (2, 4),
+            AnsiOutputFormat::ASCII => (1, 1),
+            AnsiOutputFormat::Auto | AnsiOutputFormat::Truecolor | AnsiOutputFormat::Sixel | AnsiOutputFormat::Kitty | AnsiOutputFormat::Ansi256 |
+            AnsiOutputFormat::SimpleBlack | AnsiOutputFormat::SimpleWhite => (1, 2),
+        }
+    }
+
+    /// Downgrade this format until it outputs at most the specified amount of colours,
+    /// from truecolor, quadrant blocks, or Kitty graphics to the 256-colour palette, from there or sixel graphics to the simple palette suited for the theme,
+    /// and from there to ASCII art.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use imgcatr::{AnsiOutputFormat, Theme};
+    /// assert_eq!(AnsiOutputFormat::Truecolor.capped(1 << 24, Theme::Dark), AnsiOutputFormat::Truecolor);
+    /// assert_eq!(AnsiOutputFormat::Truecolor.capped(256, Theme::Dark), AnsiOutputFormat::Ansi256);
+    /// assert_eq!(AnsiOutputFormat::Quadrant.capped(256, Theme::Dark), AnsiOutputFormat::Ansi256);
+    /// assert_eq!(AnsiOutputFormat::Truecolor.capped(16, Theme::Dark), AnsiOutputFormat::SimpleBlack);
+    /// assert_eq!(AnsiOutputFormat::Truecolor.capped(16, Theme::Light), AnsiOutputFormat::SimpleWhite);
+    /// assert_eq!(AnsiOutputFormat::Sixel.capped(256, Theme::Dark), AnsiOutputFormat::Sixel);
+    /// assert_eq!(AnsiOutputFormat::Sixel.capped(16, Theme::Dark), AnsiOutputFormat::SimpleBlack);
+    /// assert_eq!(AnsiOutputFormat::SimpleWhite.capped(8, Theme::Light), AnsiOutputFormat::ASCII);
+    /// ```
+    pub fn capped(self, max_colours: u32, theme: Theme) -> AnsiOutputFormat {
+        let mut format = self;
+        while format.colour_count() > max_colours {
+            format = match format {
+                AnsiOutputFormat::Auto | AnsiOutputFormat::Truecolor | AnsiOutputFormat::Quadrant | AnsiOutputFormat::Kitty => AnsiOutputFormat::Ansi256,
+                AnsiOutputFormat::Sixel | AnsiOutputFormat::Ansi256 => theme.simple_format(),
+                AnsiOutputFormat::SimpleBlack | AnsiOutputFormat::SimpleWhite | AnsiOutputFormat::Braille | AnsiOutputFormat::ASCII => AnsiOutputFormat::ASCII,
+            };
+        }
+        format
+    }
+
+    /// Pick the concrete format `Auto` stands for on a terminal with the specified capabilities, leaving other formats as they are.
+    ///
+    /// Detection falls back in order through
+    ///
+    ///   1. truecolor, if `caps.truecolor` (from `$COLORTERM`, `$TERM`, or `$TERM_PROGRAM`),
+    ///   2. 256 colours, if `caps.ansi256` (from `$TERM`),
+    ///   3. the simple palette suited for the theme.
+    ///
+    /// Sixel and Kitty graphics are never picked, as they don't survive terminal multiplexers and pagers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use imgcatr::{AnsiOutputFormat, TermCaps, Theme};
+    /// let caps = TermCaps { ansi256: true, ..TermCaps::default() };
+    /// assert_eq!(AnsiOutputFormat::Auto.resolve(&caps, Theme::Dark), AnsiOutputFormat::Ansi256);
+    /// assert_eq!(AnsiOutputFormat::Auto.resolve(&TermCaps { truecolor: true, ..caps }, Theme::Dark), AnsiOutputFormat::Truecolor);
+    /// assert_eq!(AnsiOutputFormat::Auto.resolve(&TermCaps::default(), Theme::Light), AnsiOutputFormat::SimpleWhite);
+    /// assert_eq!(AnsiOutputFormat::ASCII.resolve(&caps, Theme::Dark), AnsiOutputFormat::ASCII);
+    /// ```
+    pub fn resolve(self, caps: &TermCaps, theme: Theme) -> AnsiOutputFormat {
+        match self {
+            AnsiOutputFormat::Auto if caps.truecolor => AnsiOutputFormat::Truecolor,
+            AnsiOutputFormat::Auto if caps.ansi256 => AnsiOutputFormat::Ansi256,
+            AnsiOutputFormat::Auto => theme.simple_format(),
+            format => format,
+        }
+    }
+}
+
+/// How to size the image to the terminal
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ResizeMode {
+    /// Scale the image to fit inside the terminal, preserving its aspect ratio
+    Fit,
+    /// Scale the image to cover the whole terminal, preserving its aspect ratio by cropping its centre
+    Fill,
+    /// Stretch the image to the terminal's size exactly
+    Stretch,
+    /// Scale the image to the terminal's width, preserving its aspect ratio, however tall that makes it
+    FitWidth,
+    /// Scale the image to the terminal's height, preserving its aspect ratio, however wide that makes it
+    FitHeight,
+}
+
+/// Where to place the image in the terminal when it's smaller: at one of its corners or edges, or in its middle
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Anchor {
+    /// The top-left corner
+    NorthWest,
+    /// The middle of the top edge
+    North,
+    /// The top-right corner
+    NorthEast,
+    /// The middle of the left edge
+    West,
+    /// The middle of the terminal
+    Center,
+    /// The middle of the right edge
+    East,
+    /// The bottom-left corner
+    SouthWest,
+    /// The middle of the bottom edge
+    South,
+    /// The bottom-right corner
+    SouthEast,
+}
+
+/// How to compress high-dynamic-range colours into the displayable range
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ToneMapOperator {
+    /// Erik Reinhard's `c / (1 + c)`, which keeps midtones but is somewhat flat
+    Reinhard,
+    /// Krzysztof Narkowicz's fit of the ACES filmic curve, with more contrast and brighter highlights
+    Aces,
+}
+
+/// Terminal colour scheme to tune the output for
+///
+/// `Light` assumes a white background: `--ansi simple` means `simple-white`,
+/// transparent pixels are composited over white unless `--background` is given or the terminal reports its own,
+/// and ASCII art and braille map dark pixels to dense glyphs and dots.
+///
+/// `Dark` assumes a black background: `--ansi simple` means `simple-black`,
+/// transparent pixels are composited over black unless `--background` is given or the terminal reports its own,
+/// and ASCII art and braille map bright pixels to dense glyphs and dots.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Theme {
+    /// Dark text on a light background
+    Light,
+    /// Light text on a dark background
+    Dark,
+}
+
+impl Theme {
+    /// Pick the theme matching the specified background colour by its luminance.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate image;
+    /// # extern crate imgcatr;
+    /// # use imgcatr::Theme;
+    /// # use image::Rgb;
+    /// # fn main() {
+    /// assert_eq!(Theme::from_background(Rgb([0xFD, 0xF6, 0xE3])), Theme::Light);
+    /// assert_eq!(Theme::from_background(Rgb([0x00, 0x2B, 0x36])), Theme::Dark);
+    /// # }
+    /// ```
+    pub fn from_background(bg: Rgb<u8>) -> Theme {
+        if luminance(bg) > 0x7F {
+            Theme::Light
+        } else {
+            Theme::Dark
+        }
+    }
+
+    /// Guess the theme from the value of `$COLORFGBG`, as set by rxvt, Konsole, and others.
+    ///
+    /// The variable is a `;`-separated list of colour indices, the background one being last.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use imgcatr::Theme;
+    /// assert_eq!(Theme::from_colorfgbg("15;0"), Some(Theme::Dark));
+    /// assert_eq!(Theme::from_colorfgbg("0;default;15"), Some(Theme::Light));
+    /// assert_eq!(Theme::from_colorfgbg("default;default"), None);
+    /// ```
+    pub fn from_colorfgbg(var: &str) -> Option<Theme> {
+        colorfgbg_background(var).map(Theme::from_background)
+    }
+
+    /// Get the simple palette format to use on this theme's background.
+    pub fn simple_format(self) -> AnsiOutputFormat {
+        match self {
+            Theme::Light => AnsiOutputFormat::SimpleWhite,
+            Theme::Dark => AnsiOutputFormat::SimpleBlack,
+        }
+    }
+
+    /// Get the background colour this theme assumes, to composite transparent pixels over when no other is known.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate image;
+    /// # extern crate imgcatr;
+    /// # use imgcatr::Theme;
+    /// # use image::Rgb;
+    /// # fn main() {
+    /// assert_eq!(Theme::Light.background(), Rgb([0xFF, 0xFF, 0xFF]));
+    /// assert_eq!(Theme::Dark.background(), Rgb([0x00, 0x00, 0x00]));
+    /// # }
+    /// ```
+    pub fn background(self) -> Rgb<u8> {
+        match self {
+            Theme::Light => Rgb([0xFF, 0xFF, 0xFF]),
+            Theme::Dark => Rgb([0x00, 0x00, 0x00]),
+        }
+    }
+
+    /// Whether ASCII art should be drawn with dense glyphs for dark pixels.
+    pub fn invert_ascii(self) -> bool {
+        self == Theme::Light
+    }
+}
+
+/// Summary of the terminal's detected capabilities
+///
+/// Build it with `TermCaps::detect()`, or with `TermCaps::from_env()` to supply the environment yourself.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, Default)]
+pub struct TermCaps {
+    /// Whether the terminal supports 24-bit colour escapes, as advertised by `$COLORTERM` or known from `$TERM` and `$TERM_PROGRAM`.
+    pub truecolor: bool,
+    /// Whether the terminal supports the xterm 256-colour palette, as advertised by `$TERM`.
+    pub ansi256: bool,
+    /// Whether the terminal is known to support sixel graphics.
+    pub sixel: bool,
+    /// Whether the terminal is known to support the kitty graphics protocol.
+    pub kitty: bool,
+    /// The terminal's background colour, if known.
+    pub bg: Option<Rgb<u8>>,
+    /// The size of a single cell in pixels, if known.
+    pub cell_pixels: Option<(u16, u16)>,
+    /// The largest sixel image the terminal can display in pixels, if it reported one.
+    pub sixel_max: Option<(u32, u32)>,
+    /// Whether the locale's character encoding is UTF-8, if a locale is configured.
+    pub utf8: Option<bool>,
+}
+
+impl TermCaps {
+    /// Detect the capabilities of the terminal the process is running in.
+    ///
+    /// For terminals supporting sixel graphics, this asks the terminal for its maximum sixel geometry,
+    /// and, if `$COLORFGBG` isn't set, it asks the terminal for its background colour with `query_terminal_background()`,
+    /// waiting a little for the replies.
+    pub fn detect() -> TermCaps {
+        let caps = TermCaps::from_env(|var| env::var(var).ok());
+        TermCaps {
+            bg: caps.bg.or_else(query_terminal_background),
+            cell_pixels: TermCaps::detect_cell_pixels(),
+            sixel_max: if caps.sixel {
+                query_terminal(SIXEL_MAX_GEOMETRY_QUERY.as_bytes(), b"S").and_then(|reply| parse_sixel_geometry(&reply))
+            } else {
+                None
+            },
+            ..caps
+        }
+    }
+
+    /// Shrink the specified pixel size, preserving its aspect ratio, to fit in the maximum sixel geometry, if known.
+    ///
+    /// Without a known maximum the size is assumed to fit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use imgcatr::TermCaps;
+    /// let caps = TermCaps { sixel_max: Some((1000, 1000)), ..TermCaps::default() };
+    /// assert_eq!(caps.clamp_sixel_size((2000, 500)), (1000, 250));
+    /// assert_eq!(caps.clamp_sixel_size((800, 600)), (800, 600));
+    /// assert_eq!(TermCaps::default().clamp_sixel_size((5000, 5000)), (5000, 5000));
+    /// ```
+    pub fn clamp_sixel_size(&self, size: (u32, u32)) -> (u32, u32) {
+        match self.sixel_max {
+            Some((max_w, max_h)) if size.0 > max_w || size.1 > max_h => {
+                let ratio = f64::min(max_w as f64 / size.0 as f64, max_h as f64 / size.1 as f64);
+                (((size.0 as f64 * ratio) as u32).clamp(1, max_w), ((size.1 as f64 * ratio) as u32).clamp(1, max_h))
+            }
+            _ => size,
+        }
+    }
+
+    /// Work out the terminal's capabilities from the specified environment variable lookup.
+    ///
+    /// Looks at `$COLORTERM`, `$TERM`, and `$TERM_PROGRAM` for colour support, at those two and `$KITTY_WINDOW_ID`
+    /// for graphics protocols, at `$COLORFGBG` for the background colour,
+    /// and at the first set of `$LC_ALL`, `$LC_CTYPE`, and `$LANG` for the character encoding.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate image;
+    /// # extern crate imgcatr;
+    /// # use imgcatr::TermCaps;
+    /// # use image::Rgb;
+    /// # fn main() {
+    /// let caps = TermCaps::from_env(|var| match var {
+    ///     "COLORTERM" => Some("truecolor".to_string()),
+    ///     "TERM" => Some("xterm-kitty".to_string()),
+    ///     "COLORFGBG" => Some("0;15".to_string()),
+    ///     "LANG" => Some("en_GB.UTF-8".to_string()),
+    ///     _ => None,
+    /// });
+    /// assert_eq!(caps, TermCaps {
+    ///     truecolor: true,
+    ///     ansi256: true,
+    ///     sixel: false,
+    ///     kitty: true,
+    ///     bg: Some(Rgb([0xFF, 0xFF, 0xFF])),
+    ///     cell_pixels: None,
+    ///     sixel_max: None,
+    ///     utf8: Some(true),
+    /// });
+    ///
+    /// assert!(TermCaps::from_env(|var| if var == "TERM_PROGRAM" { Some("iTerm.app".to_string()) } else { None }).truecolor);
+    /// assert_eq!(TermCaps::from_env(|var| if var == "TERM" { Some("screen-256color".to_string()) } else { None }),
+    ///            TermCaps { ansi256: true, ..TermCaps::default() });
+    /// assert_eq!(TermCaps::from_env(|var| if var == "LC_CTYPE" { Some("C".to_string()) } else { None }).utf8, Some(false));
+    /// assert_eq!(TermCaps::from_env(|_| None), TermCaps::default());
+    /// # }
+    /// ```
+    pub fn from_env<F: Fn(&str) -> Option<String>>(var: F) -> TermCaps {
+        let term = var("TERM").unwrap_or_default();
+        let term_program = var("TERM_PROGRAM").unwrap_or_default();
+
+        TermCaps {
+            truecolor: matches!(var("COLORTERM").as_deref(), Some("truecolor") | Some("24bit")) || term.ends_with("-direct") || term == "xterm-kitty" ||
+                       ["iTerm.app", "WezTerm", "ghostty", "vscode"].contains(&term_program.as_str()),
+            ansi256: term.contains("256color") || term.ends_with("-direct") || term == "xterm-kitty",
+            sixel: term.contains("sixel") || term == "mlterm" || term.starts_with("foot") || term_program == "WezTerm",
+            kitty: term == "xterm-kitty" || var("KITTY_WINDOW_ID").is_some() || term_program == "WezTerm" || term_program == "ghostty",
+            bg: var("COLORFGBG").and_then(|v| colorfgbg_background(&v)),
+            cell_pixels: None,
+            sixel_max: None,
+            utf8: ["LC_ALL", "LC_CTYPE", "LANG"].iter().filter_map(|v| var(v)).find(|v| !v.is_empty()).map(|v| {
+                let v = v.to_lowercase();
+                v.contains("utf-8") || v.contains("utf8")
+            }),
+        }
+    }
+
+    #[cfg(unix)]
+    fn detect_cell_pixels() -> Option<(u16, u16)> {
+        let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+        if unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } != 0 || size.ws_col == 0 || size.ws_row == 0 {
+            return None;
+        }
+        Some((size.ws_xpixel / size.ws_col, size.ws_ypixel / size.ws_row)).filter(|&(w, h)| w != 0 && h != 0)
+    }
+
+    #[cfg(not(unix))]
+    fn detect_cell_pixels() -> Option<(u16, u16)> {
+        None
+    }
+}
+
+/// Get the background colour from a `$COLORFGBG` value, a `;`-separated list of colour indices, the background one being last.
+fn colorfgbg_background(var: &str) -> Option<Rgb<u8>> {
+    let bg = var.rsplit(';').next()?.parse::<usize>().ok()?;
+    ANSI_COLOURS_BLACK_BG.get(bg).copied()
+}
+
+
+/// Rectangular region of an image, in pixels
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct CropRect {
+    /// Column of the region's left edge.
+    pub x: u32,
+    /// Row of the region's top edge.
+    pub y: u32,
+    /// Width of the region, never `0` when parsed from the command line.
+    pub width: u32,
+    /// Height of the region, never `0` when parsed from the command line.
+    pub height: u32,
+}
+
+impl CropRect {
+    /// Check whether the region lies within an image of the specified size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use imgcatr::CropRect;
+    /// let rect = CropRect { x: 10, y: 20, width: 30, height: 40 };
+    /// assert!(rect.fits((40, 60)));
+    /// assert!(!rect.fits((39, 60)));
+    /// ```
+    pub fn fits(&self, size: (u32, u32)) -> bool {
+        self.x.checked_add(self.width).is_some_and(|right| right <= size.0) && self.y.checked_add(self.height).is_some_and(|bottom| bottom <= size.1)
+    }
+}
+
 
 /// Representation of the application's all configurable values.
-#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Options {
-    /// Image file to display.
-    /// This tuple contains the plaintext name (user-friendly) and a normalised path (programmer-friendly).
-    pub image: (String, PathBuf),
-    /// Output size. Default: detected from terminal size or no default.
+    /// Image files to display, in order.
+    /// Each tuple contains the plaintext name (user-friendly, lossily converted from non-UTF-8 paths)
+    /// and a normalised path (programmer-friendly). Default: just `"-"`, named `"<stdin>"`, to read a single image from stdin,
+    /// which is unused with `length_prefixed`.
+    pub images: Vec<(String, PathBuf)>,
+    /// The format picked by each of `images`' `:ANSI` suffix, overriding `ansi_out` for it. Default: `None` for each.
+    pub image_ansi_out: Vec<Option<AnsiOutputFormat>>,
+    /// Output size. Default: detected from terminal size, or `$COLUMNS` and `$LINES`, or `80x24`.
     pub size: (u32, u32),
-    /// Whether to preserve the image's aspect ratio when resizing. Default: `true`.
-    pub preserve_aspect: bool,
-    /// Whether to output ANSI escapes and in which format. Default: `None` on Windooze when not writing to a file.
+    /// How to size the image to `size`. Default: `ResizeMode::Fit`.
+    pub resize_mode: ResizeMode,
+    /// Zoom factor to resize the image by instead of fitting it to `size`. Default: `None`.
+    pub scale: Option<f32>,
+    /// Largest size, in cells, to display at, shrinking bigger renders whatever the terminal size or `scale`. Default: `None`.
+    pub max_size: Option<(u32, u32)>,
+    /// How many times taller than wide the terminal's cells are, for preserving the image's aspect. Default: `2.0`.
+    pub cell_aspect: f32,
+    /// How many times to repeat the image across and down, each tile sized so they all fit together. Default: `None`.
+    pub repeat: Option<(u32, u32)>,
+    /// Filter to resize the image with. Default: `FilterType::Nearest`.
+    pub filter: FilterType,
+    /// Whether to output ANSI escapes and in which format, as picked by `--ansi`.
+    /// Default: from `Options::builder()`, `Some(AnsiOutputFormat::Truecolor)`; on the command line, `--ansi auto`,
+    /// the best format `TermCaps::detect()` finds, or `None` in Windows consoles unless `--ansi` or `--output` is given.
     pub ansi_out: Option<AnsiOutputFormat>,
+    /// Terminal colour scheme to tune the output for. Default: `Theme::Dark`.
+    pub theme: Theme,
+    /// Whether to log the pipeline's progress to stderr. Default: `false`.
+    pub verbose: bool,
+    /// The terminal's capabilities, detected once. Default: `TermCaps::default()`, or on the command line `TermCaps::detect()`.
+    pub caps: TermCaps,
+    /// Whether to read length-prefixed images from stdin instead of `image`. Default: `false`.
+    pub length_prefixed: bool,
+    /// How many images to render at most when given several, `0` meaning no limit. Default: `0`.
+    pub count: usize,
+    /// Whether to copy the rendered output to the clipboard, via OSC 52, instead of displaying it. Default: `false`.
+    pub clipboard: bool,
+    /// File to write the rendered output to instead of stdout. Default: `None`.
+    pub output: Option<PathBuf>,
+    /// Size, in cells, to render at instead of `size` when writing to `output`. Default: `None`.
+    pub output_size: Option<(u32, u32)>,
+    /// How long to wait for something to read from `output` if it's a FIFO, instead of blocking until something does.
+    /// Default: `None`.
+    pub output_timeout: Option<Duration>,
+    /// Most times per second to redraw when displaying several images in turn, animating, or redrawing on resize.
+    /// Default: `60.0`.
+    pub max_fps: f32,
+    /// How many times to play animated GIFs and WebPs, `0` meaning forever. Default: `1`.
+    pub loop_count: u32,
+    /// Play only every this many frames of animated GIFs and WebPs, keeping their total duration. Default: `1`.
+    pub frame_step: usize,
+    /// Crossfade this many frames into each frame of animated GIFs and WebPs, for smoother playback. Default: `1`.
+    pub interpolate_frames: u32,
+    /// Frame of animated GIFs and WebPs to display as a still instead of playing them, or page of PDFs to display,
+    /// clamped to their last one. Default: `None`.
+    pub frame: Option<usize>,
+    /// Most terminal rows to output, clipping taller renders. Default: `None`.
+    pub max_rows: Option<u32>,
+    /// Whether clipping to `max_rows` keeps the centre of the image instead of its top. Default: `false`.
+    pub clip_centered: bool,
+    /// Whether to print how far the displayed colours are from the image's, on average, to stderr. Default: `false`.
+    pub accuracy: bool,
+    /// Whether to only list the formats this build can display. Default: `false`.
+    pub list_formats: bool,
+    /// Whether to only print the sizes and output mode each image would be rendered with to stderr, without rendering it.
+    /// Default: `false`.
+    pub dry_run: bool,
+    /// Whether to keep displaying the image, refitting and redrawing it whenever the terminal is resized. Default: `false`.
+    pub keep_alive: bool,
+    /// Whether to render the image once in each output format available, stacked and labelled. Default: `false`.
+    pub palette_preview: bool,
+    /// Whether to decode JPEGs at a reduced size close to the output size, which is faster but blurrier. Default: `false`.
+    pub fast_decode: bool,
+    /// Whether to keep memory use proportional to the output size where the format allows, per `ops::load_image_low_memory()`.
+    /// Default: `false`.
+    pub low_memory: bool,
+    /// Format to decode all images as instead of guessing it from their extension or contents. Default: `None`.
+    pub format: Option<ImageFormat>,
+    /// Whether to ignore transparency and draw the colours of transparent pixels too. Default: `false`.
+    pub no_alpha: bool,
+    /// Whether to negate the image's colours, keeping its alpha, before rendering it. Default: `false`.
+    pub invert: bool,
+    /// Whether to convert the image to its luminance before rendering it. Default: `false`.
+    pub grayscale: bool,
+    /// Whether to left-pad the output to horizontally center the image in the terminal. Default: `false`.
+    pub center: bool,
+    /// Where to place the image in the terminal, padding it with blank columns and lines,
+    /// which with just `center` is in the middle of it horizontally. Default: `None`.
+    pub anchor: Option<Anchor>,
+    /// Region of the image to display instead of all of it. Default: `None`.
+    pub crop: Option<CropRect>,
+    /// Degrees to rotate the image clockwise by, one of `90`, `180`, and `270`, before cropping and sizing it. Default: `None`.
+    pub rotate: Option<u16>,
+    /// Whether to mirror the image left to right, after rotating it. Default: `false`.
+    pub flip_h: bool,
+    /// Whether to mirror the image top to bottom, after rotating it. Default: `false`.
+    pub flip_v: bool,
+    /// Whether truecolor output draws one pixel per cell as a coloured space instead of two as a half-block. Default: `false`.
+    pub bg_only: bool,
+    /// Whether simple palette output uses 256-colour background escapes to draw lower pixels in all 16 colours,
+    /// instead of the first 8. Default: `false`.
+    pub wide_bg: bool,
+    /// Whether simple palette output approximates each cell's average colour by splitting it between two palette colours,
+    /// instead of drawing its upper and lower pixels. Default: `false`.
+    pub interpolate_palette: bool,
+    /// Colours for the simple formats to approximate to instead of the built-in ones,
+    /// with the theme's filling in for any the palette file leaves out. Default: `None`.
+    pub palette: Option<[Rgb<u8>; 16]>,
+    /// Whether to compute ASCII art brightness in linear light, which is slower but tonally more accurate. Default: `false`.
+    pub linear_luma: bool,
+    /// Glyphs ASCII art uses, from the least intense pixels to the most. Default: `ASCII_RAMP`.
+    pub ascii_ramp: Vec<char>,
+    /// Whether to flip the ASCII art ramp, and which pixels get braille dots, from what the theme picks. Default: `false`.
+    pub ascii_invert: bool,
+    /// Luminance at and above which braille dots are drawn. Default: `128`.
+    pub threshold: u8,
+    /// Whether to dither the image for the output format, with light noise for truecolor
+    /// and Floyd–Steinberg error diffusion for the palette-based formats. Default: `false`.
+    pub dither: bool,
+    /// Seed for the dithering noise. Default: derived from the current time.
+    pub seed: u64,
+    /// Exposure adjustment, in stops, for tone-mapping high-dynamic-range images. Default: `0.0`.
+    pub exposure: f32,
+    /// Operator to tone-map high-dynamic-range images with. Default: `ToneMapOperator::Reinhard`.
+    pub tonemap: ToneMapOperator,
+    /// Amount to add to each colour channel, from `-255` to `255`. Default: `0`.
+    pub brightness: i32,
+    /// Contrast adjustment, in percent, negative values reducing it. Default: `0.0`.
+    pub contrast: f32,
+    /// Gamma to raise each colour channel to the reciprocal of, values above 1 brightening the midtones. Default: `1.0`.
+    pub gamma: f32,
+    /// Colour to composite transparent pixels over, the terminal's background if detected or `theme`'s if `None`.
+    /// Default: `None`.
+    pub background: Option<Rgb<u8>>,
 }
 
 impl Options {
     /// Parse `env`-wide command-line arguments into an `Options` instance
+    ///
+    /// Exits if they're invalid, printing why, or after printing `--help` or `--version`.
     pub fn parse() -> Options {
+        Options::from_matches(Options::command().get_matches()).unwrap_or_else(|err| {
+            err.print_error(&mut stderr());
+            exit(err.exit_value());
+        })
+    }
+
+    /// Parse `env`-wide command-line arguments into an `Options` instance, like `parse()`, but without exiting.
+    ///
+    /// Invalid arguments, as well as `--help` and `--version`, give `Error::ArgumentParsingFailed` with the text clap would print,
+    /// and an undetectable terminal size, without `--size` or `--scale`, gives `Error::TerminalSizeUnknown`.
+    pub fn try_parse() -> Result<Options, Error> {
+        let matches = Options::command().try_get_matches().map_err(|err| Error::ArgumentParsingFailed(err.render().to_string()))?;
+        Options::from_matches(matches)
+    }
+
+    /// Parse the specified command-line arguments, the first being the program name, into an `Options` instance,
+    /// failing like `try_parse()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use imgcatr::{Error, Options};
+    /// let options = Options::try_parse_from(["imgcatr", "--size", "80x24", "--max-size", "40x12", "-"]).unwrap();
+    /// assert_eq!(options.max_size, Some((40, 12)));
+    ///
+    /// for size in ["ax2", "40x", "40x12x3", "2xfoo"] {
+    ///     assert!(matches!(Options::try_parse_from(["imgcatr", "--size", "80x24", "--max-size", size, "-"]),
+    ///                      Err(Error::ArgumentParsingFailed(_))));
+    ///     assert!(matches!(Options::try_parse_from(["imgcatr", "--size", "80x24", "--repeat", size, "-"]),
+    ///                      Err(Error::ArgumentParsingFailed(_))));
+    /// }
+    /// ```
+    pub fn try_parse_from<I, T>(args: I) -> Result<Options, Error>
+        where I: IntoIterator<Item = T>,
+              T: Into<OsString> + Clone
+    {
+        let matches = Options::command().try_get_matches_from(args).map_err(|err| Error::ArgumentParsingFailed(err.render().to_string()))?;
+        Options::from_matches(matches)
+    }
+
+    /// Build the command-line interface, `--size` defaulting to the terminal size,
+    /// as detected directly or, failing that, from `$COLUMNS`/`$LINES`.
+    ///
+    /// Without either, `from_matches()` fails with `Error::TerminalSizeUnknown` unless a size or scale is given.
+    fn command() -> Command {
         let mut szarg = Arg::new("size")
                             .long("size")
                             .short('s')
                             .value_name("NxM")
                             .value_parser(Options::size_validator)
                             .help("Image size to display");
-        let szarg_def;
-        let have_dimms = if let Some((w, h)) = term_size::dimensions() {
-            szarg_def = format!("{}x{}", w, h - 1);
-            szarg = szarg.default_value(&szarg_def);
-            true
-        } else {
-            szarg = szarg.required(true);
-            false
-        };
+        if let Some((w, h)) = default_output_size(term_size::dimensions()).or_else(Options::env_terminal_size) {
+            szarg = szarg.default_value(format!("{}x{}", w, h));
+        }
 
 
-        let matches = command!()
+        command!()
             .arg(Arg::new("image")
                 .value_name("IMAGE")
-                .value_parser(Options::image_file_validator)
-                .help("Image file to display")
-                .required(true))
+                .value_parser(OsStringValueParser::new().try_map(|s| Options::image_arg_validator(PathBuf::from(s))))
+                .help("Image files, directories of them, or HTTP(S) URLs to display, or - to read one from stdin, each optionally suffixed with :ANSI to override --ansi for it")
+                .num_args(1..)
+                .required_unless_present_any(["length-prefixed", "list-formats"]))
             .arg(szarg)
+            .arg(Arg::new("scale")
+                .long("scale")
+                .value_name("FACTOR")
+                .value_parser(Options::scale_validator)
+                .conflicts_with_all(["size", "force", "resize-mode", "fit-width", "fit-height"])
+                .help("Zoom the image by this factor instead of fitting it to the size"))
+            .arg(Arg::new("max-size")
+                .long("max-size")
+                .value_name("NxM")
+                .value_parser(Options::size_validator)
+                .help("Display at most this big, preserving the aspect ratio, even if the terminal or --scale is bigger"))
+            .arg(Arg::new("cell-aspect")
+                .long("cell-aspect")
+                .value_name("F")
+                .value_parser(Options::cell_aspect_validator)
+                .help("How many times taller than wide the terminal's cells are, like 1.8 to 2.2 depending on the font [default: 2.0]"))
+            .arg(Arg::new("repeat")
+                .long("repeat")
+                .short('n')
+                .value_name("CxR")
+                .value_parser(Options::size_validator)
+                .help("Tile the image C times across and R times down, to check that it repeats seamlessly"))
             .arg(Arg::new("force")
                 .long("force")
                 .short('f')
-                .help("Don't preserve the image's aspect ratio")
+                .help("Don't preserve the image's aspect ratio, same as --resize-mode stretch")
+                .conflicts_with_all(["resize-mode", "fit-width", "fit-height"])
+                .action(clap::ArgAction::SetTrue))
+            .arg(Arg::new("fit-width")
+                .long("fit-width")
+                .help("Scale the image to the terminal's width only, letting it grow as tall as it needs, same as --resize-mode fit-width")
+                .conflicts_with_all(["resize-mode", "fit-height"])
                 .action(clap::ArgAction::SetTrue))
+            .arg(Arg::new("fit-height")
+                .long("fit-height")
+                .help("Scale the image to the terminal's height only, letting it grow as wide as it needs, same as --resize-mode fit-height")
+                .conflicts_with("resize-mode")
+                .action(clap::ArgAction::SetTrue))
+            .arg(Arg::new("resize-mode")
+                .long("resize-mode")
+                .value_name("MODE")
+                .help("Fit the image inside the size, fill it by cropping the image, stretch the image to it, or fit only its width or height [default: fit]")
+                .value_parser(["fit", "fill", "stretch", "fit-width", "fit-height"]))
+            .arg(Arg::new("filter")
+                .long("filter")
+                .value_name("FILTER")
+                .help("Filter to resize the image with, smoother for photos than the default [default: nearest]")
+                .value_parser(["nearest", "triangle", "catmull-rom", "gaussian", "lanczos3"]))
             .arg(Arg::new("ansi")
                 .long("ansi")
                 .short('a')
                 .value_name("ANSI")
-                .help("Force output ANSI escape")
-                .value_parser(["truecolor", "simple-black", "simple-white", "ascii"]))
-            .get_matches();
-
-        let image: &String = matches.get_one::<String>("image").unwrap();
-        Options { image: (image.to_string(), fs::canonicalize(image).unwrap()),
-                  size: *matches.get_one::<(u32, u32)>("size").unwrap(),
-                  preserve_aspect: !matches.get_flag("force"),
-                  ansi_out: if cfg!(not(target_os = "windows")) || !have_dimms || matches.contains_id("ansi") {
-                    match matches.get_one::<String>("ansi").map(|x| x.as_str()).unwrap_or("truecolor") {
-                        "truecolor" => Some(AnsiOutputFormat::Truecolor),
-                        "simple-black" => Some(AnsiOutputFormat::SimpleBlack),
-                        "simple-white" => Some(AnsiOutputFormat::SimpleWhite),
-                        "ascii" => Some(AnsiOutputFormat::ASCII),
-                        _ => unreachable!(),
+                .help("Force output ANSI escape, instead of the best one detected of truecolor, 256, and simple [default: auto]")
+                .value_parser(ANSI_FORMAT_NAMES))
+            .arg(Arg::new("force-utf8-check")
+                .long("force-utf8-check")
+                .value_name("on|off")
+                .help("Fall back to ASCII art if the locale isn't UTF-8 [default: on]")
+                .value_parser(["on", "off"]))
+            .arg(Arg::new("max-colours")
+                .long("max-terminal-colors")
+                .value_name("N")
+                .value_parser(clap::value_parser!(u32))
+                .help("Downgrade the output format until it uses at most this many colours"))
+            .arg(Arg::new("theme")
+                .long("theme")
+                .value_name("THEME")
+                .help("Terminal colour scheme to tune the output for: light picks simple-white, a white background for transparency, \
+                       and dense ASCII and braille for dark pixels; dark the opposite; auto the one matching the terminal [default: dark]")
+                .value_parser(["light", "dark", "auto"]))
+            .arg(Arg::new("max-fps")
+                .long("max-fps")
+                .value_name("FPS")
+                .value_parser(Options::max_fps_validator)
+                .help("Redraw at most this many times per second [default: 60]"))
+            .arg(Arg::new("loop")
+                .long("loop")
+                .short('l')
+                .value_name("N")
+                .value_parser(clap::value_parser!(u32))
+                .help("Play animated GIFs and WebPs this many times, 0 for forever [default: 1]"))
+            .arg(Arg::new("frame-step")
+                .long("frame-step")
+                .value_name("N")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .help("Play only every Nth frame of animated GIFs and WebPs [default: 1]"))
+            .arg(Arg::new("interpolate-frames")
+                .long("interpolate-frames")
+                .value_name("FACTOR")
+                .value_parser(clap::value_parser!(u32).range(1..))
+                .help("Crossfade between frames of animated GIFs and WebPs, playing FACTOR frames for each [default: 1]"))
+            .arg(Arg::new("frame")
+                .long("frame")
+                .value_name("N")
+                .value_parser(clap::value_parser!(usize))
+                .help("Display only the Nth frame, counting from 0, of animated GIFs and WebPs as a still, or page of PDFs"))
+            .arg(Arg::new("max-rows")
+                .long("max-rows")
+                .value_name("N")
+                .value_parser(clap::value_parser!(u32).range(1..))
+                .help("Clip the output to at most this many rows"))
+            .arg(Arg::new("clip-from")
+                .long("clip-from")
+                .value_name("WHERE")
+                .requires("max-rows")
+                .help("Which part of the image to keep when clipping to --max-rows [default: top]")
+                .value_parser(["top", "center"]))
+            .arg(Arg::new("accuracy")
+                .long("accuracy")
+                .help("Report the mean colour error of the output to stderr")
+                .action(clap::ArgAction::SetTrue))
+            .arg(Arg::new("keep-alive")
+                .long("keep-alive")
+                .help("Keep displaying the image, redrawing it to fit when the terminal is resized")
+                .conflicts_with_all(["length-prefixed", "clipboard"])
+                .action(clap::ArgAction::SetTrue))
+            .arg(Arg::new("palette-preview")
+                .long("palette-preview")
+                .help("Render the image in each available ANSI format, to pick the best-looking one")
+                .conflicts_with("clipboard")
+                .action(clap::ArgAction::SetTrue))
+            .arg(Arg::new("fast-decode")
+                .long("fast-decode")
+                .help("Decode JPEGs at a reduced size close to the output size, for speed")
+                .action(clap::ArgAction::SetTrue))
+            .arg(Arg::new("low-memory")
+                .long("low-memory")
+                .help("Decode JPEGs at a reduced size and PNGs a row at a time, so memory use follows the output size, not the image's")
+                .action(clap::ArgAction::SetTrue))
+            .arg(Arg::new("format")
+                .long("format")
+                .value_name("FORMAT")
+                .help("Decode images as this format instead of guessing it, named by any of its extensions, like png or jpg")
+                .value_parser(Options::format_validator))
+            .arg(Arg::new("no-alpha")
+                .long("no-alpha")
+                .help("Ignore transparency, drawing the colours hidden behind it")
+                .action(clap::ArgAction::SetTrue))
+            .arg(Arg::new("invert")
+                .long("invert")
+                .short('i')
+                .help("Negate the image's colours, e.g. for white-background documents on dark terminals")
+                .action(clap::ArgAction::SetTrue))
+            .arg(Arg::new("grayscale")
+                .long("grayscale")
+                .short('g')
+                .help("Render the image in shades of grey")
+                .action(clap::ArgAction::SetTrue))
+            .arg(Arg::new("center")
+                .long("center")
+                .short('c')
+                .help("Center the image horizontally in the terminal")
+                .action(clap::ArgAction::SetTrue))
+            .arg(Arg::new("anchor")
+                .long("anchor")
+                .value_name("WHERE")
+                .help("Place the image at this corner or edge of the terminal, or in its middle with c, padding it both ways")
+                .value_parser(["nw", "n", "ne", "w", "c", "e", "sw", "s", "se"]))
+            .arg(Arg::new("crop")
+                .long("crop")
+                .value_name("X,Y,W,H")
+                .value_parser(Options::crop_validator)
+                .help("Display only the W by H pixel region with its top-left corner at X,Y"))
+            .arg(Arg::new("rotate")
+                .long("rotate")
+                .value_name("DEGREES")
+                .value_parser(Options::rotate_validator)
+                .help("Rotate the image clockwise by 90, 180, or 270 degrees, before cropping it"))
+            .arg(Arg::new("flip-h")
+                .long("flip-h")
+                .help("Mirror the image left to right, after rotating it")
+                .action(clap::ArgAction::SetTrue))
+            .arg(Arg::new("flip-v")
+                .long("flip-v")
+                .help("Mirror the image top to bottom, after rotating it")
+                .action(clap::ArgAction::SetTrue))
+            .arg(Arg::new("bg-only")
+                .long("bg-only")
+                .help("Draw truecolor output with coloured spaces, one pixel per cell, instead of half-blocks")
+                .action(clap::ArgAction::SetTrue))
+            .arg(Arg::new("wide-bg")
+                .long("wide-bg")
+                .help("Use all 16 colours for backgrounds in simple output, via 256-colour escapes")
+                .action(clap::ArgAction::SetTrue))
+            .arg(Arg::new("interpolate-palette")
+                .long("interpolate-palette")
+                .help("Blend two palette colours per cell in simple output, trading detail for more apparent colours")
+                .action(clap::ArgAction::SetTrue))
+            .arg(Arg::new("palette")
+                .long("palette")
+                .value_name("FILE")
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("Approximate to the terminal palette's colours in FILE, as #RRGGBB lines, GIMP .gpl, or JASC .pal, in simple output"))
+            .arg(Arg::new("linear-luma")
+                .long("linear-luma")
+                .help("Compute ASCII art brightness in linear light, for better tones")
+                .action(clap::ArgAction::SetTrue))
+            .arg(Arg::new("ascii-ramp")
+                .long("ascii-ramp")
+                .value_name("GLYPHS")
+                .value_parser(Options::ascii_ramp_validator)
+                .help("Glyphs for ASCII art, from the least intense pixels to the most [default: \" .,-~+=@\"]"))
+            .arg(Arg::new("ascii-invert")
+                .long("ascii-invert")
+                .help("Flip which pixels get the dense glyphs in ASCII art, or the dots in braille art")
+                .action(clap::ArgAction::SetTrue))
+            .arg(Arg::new("threshold")
+                .long("threshold")
+                .value_name("LUMA")
+                .value_parser(clap::value_parser!(u8))
+                .help("Luminance from which pixels get a dot in braille art [default: 128]"))
+            .arg(Arg::new("dither")
+                .long("dither")
+                .help("Dither the image to reduce colour banding, by error diffusion for palette output")
+                .action(clap::ArgAction::SetTrue))
+            .arg(Arg::new("seed")
+                .long("seed")
+                .value_name("SEED")
+                .value_parser(clap::value_parser!(u64))
+                .help("Seed for the dithering noise, for reproducible output"))
+            .arg(Arg::new("exposure")
+                .long("exposure")
+                .value_name("STOPS")
+                .value_parser(Options::exposure_validator)
+                .allow_negative_numbers(true)
+                .help("Exposure adjustment for HDR and EXR images"))
+            .arg(Arg::new("tonemap")
+                .long("tonemap")
+                .value_name("OPERATOR")
+                .help("Tone-map HDR and EXR images with the Reinhard operator or the ACES filmic curve [default: reinhard]")
+                .value_parser(["reinhard", "aces"]))
+            .arg(Arg::new("brightness")
+                .long("brightness")
+                .value_name("N")
+                .value_parser(clap::value_parser!(i32).range(-255..=255))
+                .allow_negative_numbers(true)
+                .help("Add this to each colour channel, from -255 to 255 [default: 0]"))
+            .arg(Arg::new("background")
+                .long("background")
+                .value_name("R,G,B")
+                .value_parser(Options::background_validator)
+                .help("Colour to draw transparent pixels over [default: the terminal's background, or black]"))
+            .arg(Arg::new("contrast")
+                .long("contrast")
+                .value_name("PERCENT")
+                .value_parser(Options::contrast_validator)
+                .allow_negative_numbers(true)
+                .help("Increase the contrast by this much, or decrease it if negative [default: 0]"))
+            .arg(Arg::new("gamma")
+                .long("gamma")
+                .value_name("GAMMA")
+                .value_parser(Options::gamma_validator)
+                .help("Gamma-correct the image, values above 1 brightening the midtones [default: 1]"))
+            .arg(Arg::new("length-prefixed")
+                .long("length-prefixed")
+                .help("Read images from stdin, each preceded by its length as a 4-byte big-endian number")
+                .conflicts_with("image")
+                .action(clap::ArgAction::SetTrue))
+            .arg(Arg::new("count")
+                .long("count")
+                .value_name("N")
+                .value_parser(clap::value_parser!(usize))
+                .help("Render at most this many images, 0 for no limit"))
+            .arg(Arg::new("clipboard")
+                .long("clipboard")
+                .help("Copy the output to the clipboard instead of displaying it")
+                .action(clap::ArgAction::SetTrue))
+            .arg(Arg::new("output")
+                .long("output")
+                .short('o')
+                .value_name("PATH")
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("Write the output to this file instead of displaying it; a FIFO blocks until something reads from it")
+                .conflicts_with_all(["clipboard", "keep-alive"]))
+            .arg(Arg::new("output-size")
+                .long("output-size")
+                .value_name("WxH")
+                .value_parser(Options::size_validator)
+                .help("Size to render at when writing to --output, instead of --size")
+                .requires("output"))
+            .arg(Arg::new("timeout")
+                .long("timeout")
+                .value_name("SECONDS")
+                .value_parser(Options::timeout_validator)
+                .help("Give up on an --output FIFO if nothing reads from it within this many seconds, instead of blocking")
+                .requires("output"))
+            .arg(Arg::new("list-formats")
+                .long("list-formats")
+                .visible_alias("probe-only")
+                .help("List the formats this build can display and exit")
+                .action(clap::ArgAction::SetTrue))
+            .arg(Arg::new("dry-run")
+                .long("dry-run")
+                .help("Print the source, terminal, and target sizes and the output mode to stderr instead of rendering")
+                .action(clap::ArgAction::SetTrue))
+            .arg(Arg::new("verbose")
+                .long("verbose")
+                .short('v')
+                .help("Log what's being done to stderr")
+                .action(clap::ArgAction::SetTrue))
+    }
+
+    fn from_matches(matches: ArgMatches) -> Result<Options, Error> {
+        let term_dimms = default_output_size(term_size::dimensions());
+        let env_dimms = if term_dimms.is_none() {
+            Options::env_terminal_size()
+        } else {
+            None
+        };
+        let have_dimms = term_dimms.or(env_dimms).is_some();
+        let verbose = matches.get_flag("verbose");
+        let caps = TermCaps::detect();
+        if verbose {
+            eprintln!("Terminal capabilities: {:?}", caps);
+            match (term_dimms, env_dimms) {
+                (Some((w, h)), _) => eprintln!("Detected terminal size: {}x{}", w, h),
+                (None, Some((w, h))) => eprintln!("Terminal size not detected, using $COLUMNS/$LINES: {}x{}", w, h),
+                (None, None) => eprintln!("Terminal size not detected"),
+            }
+        }
+
+        let theme = match matches.get_one::<String>("theme").map(|x| x.as_str()).unwrap_or("dark") {
+            "light" => Theme::Light,
+            "dark" => Theme::Dark,
+            "auto" => {
+                caps.bg.map(Theme::from_background).unwrap_or_else(|| {
+                    if verbose {
+                        eprintln!("Theme not detected from $COLORFGBG or the terminal, assuming dark");
                     }
-                } else {
-                    None
-                },
+                    Theme::Dark
+                })
+            }
+            _ => unreachable!(),
+        };
+
+        // Without an explicit theme, pick the simple palette for the terminal's actual background
+        let simple_theme = if matches.contains_id("theme") {
+            theme
+        } else {
+            caps.bg.map(Theme::from_background).unwrap_or(theme)
+        };
+
+        let mut images: Vec<_> = matches.get_many::<(PathBuf, Option<String>)>("image")
+            .into_iter()
+            .flatten()
+            .flat_map(|(path, ansi)| if path.is_dir() {
+                Options::directory_images(path, verbose).into_iter().map(|path| (path, ansi.clone())).collect()
+            } else {
+                vec![(path.clone(), ansi.clone())]
+            })
+            .collect();
+        if !matches.contains_id("image") {
+            images.push((PathBuf::from("-"), None));
+        }
+        let pick_ansi = |image_ansi: Option<&str>| {
+            let mut ansi_out = if cfg!(not(target_os = "windows")) || !have_dimms || matches.contains_id("ansi") || image_ansi.is_some() ||
+                               matches.contains_id("output") {
+                let name = image_ansi.or_else(|| matches.get_one::<String>("ansi").map(|x| x.as_str())).unwrap_or("auto");
+                let ansi = AnsiOutputFormat::from_name(name, simple_theme).unwrap();
+                let resolved = ansi.resolve(&caps, simple_theme);
+                if verbose && resolved != ansi {
+                    eprintln!("Detected output mode {:?}", resolved);
+                }
+                Some(resolved)
+            } else {
+                None
+            };
+            if let (Some(ansi), Some(&max_colours)) = (ansi_out, matches.get_one::<u32>("max-colours")) {
+                let capped = ansi.capped(max_colours, simple_theme);
+                if verbose && capped != ansi {
+                    eprintln!("Downgraded output mode from {:?} to {:?} to use at most {} colours", ansi, capped, max_colours);
+                }
+                ansi_out = Some(capped);
+            }
+            if let Some(ansi) = ansi_out.filter(|&ansi| !matches!(ansi, AnsiOutputFormat::ASCII | AnsiOutputFormat::Sixel | AnsiOutputFormat::Kitty)) {
+                if caps.utf8 == Some(false) && matches.get_one::<String>("force-utf8-check").map(|x| x.as_str()) != Some("off") {
+                    if verbose {
+                        eprintln!("Locale isn't UTF-8, falling back from {:?} to ASCII art", ansi);
+                    }
+                    ansi_out = Some(AnsiOutputFormat::ASCII);
+                }
+            }
+            ansi_out
+        };
+        let ansi_out = pick_ansi(None);
+        let image_ansi_out = images.iter().map(|(_, ansi)| ansi.as_deref().and_then(|ansi| pick_ansi(Some(ansi)))).collect();
+
+        let resize_mode = match matches.get_one::<String>("resize-mode").map(|x| x.as_str()) {
+            Some("fill") => ResizeMode::Fill,
+            Some("stretch") => ResizeMode::Stretch,
+            Some("fit-width") => ResizeMode::FitWidth,
+            Some("fit-height") => ResizeMode::FitHeight,
+            _ if matches.get_flag("force") => ResizeMode::Stretch,
+            _ if matches.get_flag("fit-width") => ResizeMode::FitWidth,
+            _ if matches.get_flag("fit-height") => ResizeMode::FitHeight,
+            _ => ResizeMode::Fit,
+        };
+        let mut builder = Options::builder().resize_mode(resize_mode);
+        match matches.get_one::<(u32, u32)>("size") {
+            Some(&size) => builder = builder.size(size),
+            None if !have_dimms && !matches.contains_id("scale") && !matches.contains_id("output-size") && !matches.get_flag("list-formats") => return Err(Error::TerminalSizeUnknown),
+            None => {}
         }
+        if let Some(ansi) = ansi_out {
+            builder = builder.ansi(ansi);
+        }
+        let defaults = builder.build()?;
+
+        let palette = match matches.get_one::<PathBuf>("palette") {
+            Some(path) => {
+                let text = fs::read_to_string(path).map_err(|_| Error::InvalidPalette(format!("couldn't read \"{}\"", path.display())))?;
+                let mut palette = match simple_theme.simple_format() {
+                    AnsiOutputFormat::SimpleWhite => ANSI_COLOURS_WHITE_BG,
+                    _ => ANSI_COLOURS_BLACK_BG,
+                };
+                for (slot, clr) in palette.iter_mut().zip(parse_palette(&text)?) {
+                    *slot = clr;
+                }
+                Some(palette)
+            }
+            None => None,
+        };
+
+        Ok(Options { scale: matches.get_one::<f32>("scale").copied(),
+                     max_size: matches.get_one::<(u32, u32)>("max-size").copied(),
+                     cell_aspect: matches.get_one::<f32>("cell-aspect").copied().unwrap_or(defaults.cell_aspect),
+                     repeat: matches.get_one::<(u32, u32)>("repeat").copied(),
+                     filter: match matches.get_one::<String>("filter").map(|x| x.as_str()).unwrap_or("nearest") {
+                         "nearest" => FilterType::Nearest,
+                         "triangle" => FilterType::Triangle,
+                         "catmull-rom" => FilterType::CatmullRom,
+                         "gaussian" => FilterType::Gaussian,
+                         "lanczos3" => FilterType::Lanczos3,
+                         _ => unreachable!(),
+                     },
+                     // Missing files are only reported when they're displayed, so they don't stop the others
+                     images: images.into_iter()
+                         .map(|(path, _)| if path == Path::new("-") {
+                             ("<stdin>".to_string(), path)
+                         } else if is_url(&path) {
+                             (path.display().to_string(), path)
+                         } else {
+                             (path.display().to_string(), fs::canonicalize(&path).unwrap_or(path))
+                         })
+                         .collect(),
+                     ansi_out,
+                     image_ansi_out,
+                     theme,
+                     verbose,
+                     caps,
+                     length_prefixed: matches.get_flag("length-prefixed"),
+                     count: matches.get_one::<usize>("count").copied().unwrap_or(defaults.count),
+                     clipboard: matches.get_flag("clipboard"),
+                     output: matches.get_one::<PathBuf>("output").cloned(),
+                     output_size: matches.get_one::<(u32, u32)>("output-size").copied(),
+                     output_timeout: matches.get_one::<Duration>("timeout").copied(),
+                     max_fps: matches.get_one::<f32>("max-fps").copied().unwrap_or(defaults.max_fps),
+                     loop_count: matches.get_one::<u32>("loop").copied().unwrap_or(defaults.loop_count),
+                     frame_step: matches.get_one::<u64>("frame-step").map(|&n| n as usize).unwrap_or(defaults.frame_step),
+                     interpolate_frames: matches.get_one::<u32>("interpolate-frames").copied().unwrap_or(defaults.interpolate_frames),
+                     frame: matches.get_one::<usize>("frame").copied(),
+                     max_rows: matches.get_one::<u32>("max-rows").copied(),
+                     clip_centered: matches.get_one::<String>("clip-from").map(|x| x.as_str()) == Some("center"),
+                     accuracy: matches.get_flag("accuracy"),
+                     list_formats: matches.get_flag("list-formats"),
+                     dry_run: matches.get_flag("dry-run"),
+                     keep_alive: matches.get_flag("keep-alive"),
+                     palette_preview: matches.get_flag("palette-preview"),
+                     fast_decode: matches.get_flag("fast-decode"),
+                     low_memory: matches.get_flag("low-memory"),
+                     format: matches.get_one::<ImageFormat>("format").copied(),
+                     no_alpha: matches.get_flag("no-alpha"),
+                     invert: matches.get_flag("invert"),
+                     grayscale: matches.get_flag("grayscale"),
+                     center: matches.get_flag("center"),
+                     anchor: matches.get_one::<String>("anchor").map(|x| match x.as_str() {
+                         "nw" => Anchor::NorthWest,
+                         "n" => Anchor::North,
+                         "ne" => Anchor::NorthEast,
+                         "w" => Anchor::West,
+                         "e" => Anchor::East,
+                         "sw" => Anchor::SouthWest,
+                         "s" => Anchor::South,
+                         "se" => Anchor::SouthEast,
+                         _ => Anchor::Center,
+                     }),
+                     crop: matches.get_one::<CropRect>("crop").copied(),
+                     rotate: matches.get_one::<u16>("rotate").copied(),
+                     flip_h: matches.get_flag("flip-h"),
+                     flip_v: matches.get_flag("flip-v"),
+                     bg_only: matches.get_flag("bg-only"),
+                     wide_bg: matches.get_flag("wide-bg"),
+                     interpolate_palette: matches.get_flag("interpolate-palette"),
+                     palette,
+                     linear_luma: matches.get_flag("linear-luma"),
+                     ascii_ramp: matches.get_one::<Vec<char>>("ascii-ramp").cloned().unwrap_or_else(|| defaults.ascii_ramp.clone()),
+                     ascii_invert: matches.get_flag("ascii-invert"),
+                     threshold: matches.get_one::<u8>("threshold").copied().unwrap_or(defaults.threshold),
+                     dither: matches.get_flag("dither"),
+                     seed: matches.get_one::<u64>("seed").copied().unwrap_or(defaults.seed),
+                     exposure: matches.get_one::<f32>("exposure").copied().unwrap_or(defaults.exposure),
+                     tonemap: match matches.get_one::<String>("tonemap").map(|x| x.as_str()) {
+                         Some("aces") => ToneMapOperator::Aces,
+                         _ => defaults.tonemap,
+                     },
+                     brightness: matches.get_one::<i32>("brightness").copied().unwrap_or(defaults.brightness),
+                     contrast: matches.get_one::<f32>("contrast").copied().unwrap_or(defaults.contrast),
+                     gamma: matches.get_one::<f32>("gamma").copied().unwrap_or(defaults.gamma),
+                     background: matches.get_one::<Rgb<u8>>("background").copied(),
+                     ..defaults })
+    }
+
+    /// Start building an `Options` instance, for using imgcatr as a library without going through the command line.
+    pub fn builder() -> OptionsBuilder {
+        OptionsBuilder { images: Vec::new(), size: None, resize_mode: ResizeMode::Fit, ansi_out: AnsiOutputFormat::Truecolor }
     }
 
-    fn image_file_validator(s: &str) -> Result<String, String> {
-        let path: Result<PathBuf, std::io::Error>  = fs::canonicalize(&s);
-        match path {
-            Err(_) => Err(format!("Image file \"{}\" not found", s)),
-            _ => Ok(s.to_string()),
+    /// Terminal size from `$COLUMNS`/`$LINES`, for when the terminal reports nothing usable.
+    fn env_terminal_size() -> Option<(u32, u32)> {
+        let env_dim = |var| env::var(var).ok().and_then(|v| v.parse::<usize>().ok());
+        default_output_size(env_dim("COLUMNS").zip(env_dim("LINES")))
+    }
+
+    /// Get the files in the specified directory whose format can be guessed, sorted by name,
+    /// or just the directory if it can't be read, to fail when it's displayed.
+    fn directory_images(dir: &Path, verbose: bool) -> Vec<PathBuf> {
+        let mut paths = match fs::read_dir(dir) {
+            Ok(entries) => entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).filter(|path| path.is_file()).collect::<Vec<_>>(),
+            Err(_) => return vec![dir.to_path_buf()],
+        };
+        paths.sort();
+        paths.retain(|path| {
+            let image = guess_format(&(path.display().to_string(), path.clone())).is_ok();
+            if verbose && !image {
+                eprintln!("Skipping \"{}\", which isn't an image", path.display());
+            }
+            image
+        });
+        paths
+    }
+
+    /// Split an optional `:ANSI` output format suffix off the image path, unless the whole thing names an existing file.
+    /// The display name is converted lossily, so it may not round-trip, but the path keeps the original bytes.
+    fn image_arg_validator(s: PathBuf) -> Result<(PathBuf, Option<String>), String> {
+        if !s.exists() {
+            if let Some((path, ansi)) = s.to_str().and_then(|s| s.rsplit_once(':')) {
+                if ANSI_FORMAT_NAMES.contains(&ansi) {
+                    return Ok((PathBuf::from(path), Some(ansi.to_string())));
+                } else if Path::new(path).exists() {
+                    return Err(format!("Unknown output format \"{}\" for image file \"{}\", expected one of: {}",
+                                       ansi,
+                                       path,
+                                       ANSI_FORMAT_NAMES.join(", ")));
+                }
+            }
         }
+        Ok((s, None))
     }
 
     fn parse_size(s: &str) -> Option<(u32, u32)> {
-        let mut parts = s.splitn(2, |c| c == 'x' || c == 'X');
-        let x : u32 =  parts.next()?.parse::<u32>().unwrap();
-        let y : u32 = parts.next()?.parse::<u32>().unwrap();
+        let mut parts = s.splitn(2, ['x', 'X']);
+        let x : u32 = parts.next()?.parse::<u32>().ok()?;
+        let y : u32 = parts.next()?.parse::<u32>().ok()?;
         Some((x, y))
     }
     
     fn size_validator(s: &str) -> Result<(u32, u32), String> {
-        match Options::parse_size(&s) {
+        match Options::parse_size(s) {
             None => Err(format!("\"{}\" is not a valid size (in format \"NNNxMMM\")", s)),
-            Some((0, _)) | Some((_, 0)) => Err(format!("Can't resize image to size 0")),
+            Some((0, _)) | Some((_, 0)) => Err("Can't resize image to size 0".to_string()),
             size => Ok(size.unwrap()),
         }
     }
+
+    fn crop_validator(s: &str) -> Result<CropRect, String> {
+        let parts = s.split(',').map(|part| part.trim().parse::<u32>()).collect::<Result<Vec<_>, _>>();
+        match parts.as_deref() {
+            Ok(&[_, _, 0, _]) | Ok(&[_, _, _, 0]) => Err("Can't crop image to size 0".to_string()),
+            Ok(&[x, y, width, height]) => Ok(CropRect { x, y, width, height }),
+            _ => Err(format!("\"{}\" is not a valid crop rectangle (in format \"X,Y,W,H\")", s)),
+        }
+    }
+
+    fn background_validator(s: &str) -> Result<Rgb<u8>, String> {
+        match s.split(',').map(|part| part.trim().parse::<u8>()).collect::<Result<Vec<_>, _>>().as_deref() {
+            Ok(&[r, g, b]) => Ok(Rgb([r, g, b])),
+            _ => Err(format!("\"{}\" is not a valid colour (in format \"R,G,B\", each from 0 to 255)", s)),
+        }
+    }
+
+    fn exposure_validator(s: &str) -> Result<f32, String> {
+        match s.parse::<f32>() {
+            Ok(exposure) if exposure.is_finite() => Ok(exposure),
+            _ => Err(format!("\"{}\" is not a valid exposure", s)),
+        }
+    }
+
+    fn contrast_validator(s: &str) -> Result<f32, String> {
+        match s.parse::<f32>() {
+            Ok(contrast) if contrast.is_finite() => Ok(contrast),
+            _ => Err(format!("\"{}\" is not a valid contrast", s)),
+        }
+    }
+
+    fn gamma_validator(s: &str) -> Result<f32, String> {
+        match s.parse::<f32>() {
+            Ok(gamma) if gamma.is_finite() && gamma > 0.0 => Ok(gamma),
+            Ok(_) => Err(format!("Gamma \"{}\" must be a positive number", s)),
+            Err(_) => Err(format!("\"{}\" is not a valid gamma", s)),
+        }
+    }
+
+    fn scale_validator(s: &str) -> Result<f32, String> {
+        match s.parse::<f32>() {
+            Ok(scale) if scale.is_finite() && scale > 0.0 => Ok(scale),
+            Ok(_) => Err(format!("Scale \"{}\" must be a positive number", s)),
+            Err(_) => Err(format!("\"{}\" is not a valid scale", s)),
+        }
+    }
+
+    fn rotate_validator(s: &str) -> Result<u16, String> {
+        match s.parse::<u16>() {
+            Ok(degrees @ (90 | 180 | 270)) => Ok(degrees),
+            _ => Err(format!("\"{}\" is not a valid rotation (90, 180, or 270)", s)),
+        }
+    }
+
+    fn cell_aspect_validator(s: &str) -> Result<f32, String> {
+        match s.parse::<f32>() {
+            Ok(aspect) if aspect.is_finite() && aspect > 0.0 => Ok(aspect),
+            Ok(_) => Err(format!("Cell aspect \"{}\" must be a positive number", s)),
+            Err(_) => Err(format!("\"{}\" is not a valid cell aspect", s)),
+        }
+    }
+
+    fn format_validator(s: &str) -> Result<ImageFormat, String> {
+        let formats = decodable_formats();
+        formats.iter()
+            .find(|f| f.extensions_str().iter().any(|ext| ext.eq_ignore_ascii_case(s)))
+            .copied()
+            .ok_or_else(|| {
+                format!("\"{}\" is not a format this build can decode ({})",
+                        s,
+                        formats.iter().filter_map(|f| f.extensions_str().first()).copied().collect::<Vec<_>>().join(", "))
+            })
+    }
+
+    fn timeout_validator(s: &str) -> Result<Duration, String> {
+        match s.parse::<f32>() {
+            Ok(secs) if secs.is_finite() && secs >= 0.0 => Ok(Duration::from_secs_f32(secs)),
+            Ok(_) => Err(format!("Timeout \"{}\" must be a non-negative number", s)),
+            Err(_) => Err(format!("\"{}\" is not a valid timeout", s)),
+        }
+    }
+
+    fn max_fps_validator(s: &str) -> Result<f32, String> {
+        match s.parse::<f32>() {
+            Ok(fps) if fps.is_finite() && fps > 0.0 => Ok(fps),
+            Ok(_) => Err(format!("Frame rate \"{}\" must be a positive number", s)),
+            Err(_) => Err(format!("\"{}\" is not a valid frame rate", s)),
+        }
+    }
+
+    fn ascii_ramp_validator(s: &str) -> Result<Vec<char>, String> {
+        let ramp = s.chars().collect::<Vec<_>>();
+        if ramp.len() < 2 {
+            Err(format!("ASCII ramp \"{}\" needs at least 2 glyphs", s))
+        } else {
+            Ok(ramp)
+        }
+    }
+}
+
+
+/// Builder for `Options`, validating them without touching the command line or exiting
+///
+/// Everything it doesn't set takes the default listed on `Options`.
+///
+/// # Examples
+///
+/// ```
+/// # use imgcatr::{AnsiOutputFormat, Error, Options, ResizeMode};
+/// let options = Options::builder().image("-").size((40, 20)).resize_mode(ResizeMode::Stretch).ansi(AnsiOutputFormat::Ansi256).build().unwrap();
+/// assert_eq!(options.images, [("<stdin>".to_string(), "-".into())]);
+/// assert_eq!(options.size, (40, 20));
+/// assert_eq!(options.resize_mode, ResizeMode::Stretch);
+///
+/// assert_eq!(Options::builder().image("nonexistent.png").build(), Err(Error::OpeningImageFailed("nonexistent.png".to_string())));
+/// assert!(Options::builder().size((0, 20)).build().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptionsBuilder {
+    images: Vec<PathBuf>,
+    size: Option<(u32, u32)>,
+    resize_mode: ResizeMode,
+    ansi_out: AnsiOutputFormat,
+}
+
+impl OptionsBuilder {
+    /// Add an image file to display after those added so far, or `"-"` to read one from stdin.
+    pub fn image<P: Into<PathBuf>>(mut self, path: P) -> OptionsBuilder {
+        self.images.push(path.into());
+        self
+    }
+
+    /// Set the output size, in cells.
+    pub fn size(mut self, size: (u32, u32)) -> OptionsBuilder {
+        self.size = Some(size);
+        self
+    }
+
+    /// Set how to size the image to the output size.
+    pub fn resize_mode(mut self, resize_mode: ResizeMode) -> OptionsBuilder {
+        self.resize_mode = resize_mode;
+        self
+    }
+
+    /// Set the ANSI output format.
+    pub fn ansi(mut self, ansi: AnsiOutputFormat) -> OptionsBuilder {
+        self.ansi_out = ansi;
+        self
+    }
+
+    /// Validate the options so far and fill in the rest.
+    ///
+    /// Fails with `Error::OpeningImageFailed` if an image file, other than an HTTP(S) URL, doesn't exist,
+    /// or with `Error::InvalidOption` if the size is 0 in either direction.
+    pub fn build(self) -> Result<Options, Error> {
+        let images = if self.images.is_empty() {
+            vec![("<stdin>".to_string(), PathBuf::from("-"))]
+        } else {
+            self.images
+                .into_iter()
+                .map(|path| if path == Path::new("-") {
+                    Ok(("<stdin>".to_string(), path))
+                } else if is_url(&path) {
+                    Ok((path.display().to_string(), path))
+                } else {
+                    let name = path.display().to_string();
+                    fs::canonicalize(&path).map(|path| (name.clone(), path)).map_err(|_| Error::OpeningImageFailed(name))
+                })
+                .collect::<Result<_, _>>()?
+        };
+        let size = match self.size {
+            Some((0, _)) | Some((_, 0)) => return Err(Error::InvalidOption("can't resize image to size 0".to_string())),
+            Some(size) => size,
+            None => default_output_size(term_size::dimensions()).or_else(Options::env_terminal_size).unwrap_or((80, 24)),
+        };
+
+        Ok(Options {
+            image_ansi_out: vec![None; images.len()],
+            images,
+            size,
+            resize_mode: self.resize_mode,
+            scale: None,
+            max_size: None,
+            cell_aspect: 2.0,
+            repeat: None,
+            filter: FilterType::Nearest,
+            ansi_out: Some(self.ansi_out),
+            theme: Theme::Dark,
+            verbose: false,
+            caps: TermCaps::default(),
+            length_prefixed: false,
+            count: 0,
+            clipboard: false,
+            output: None,
+            output_size: None,
+            output_timeout: None,
+            max_fps: 60.0,
+            loop_count: 1,
+            frame_step: 1,
+            interpolate_frames: 1,
+            frame: None,
+            max_rows: None,
+            clip_centered: false,
+            accuracy: false,
+            list_formats: false,
+            dry_run: false,
+            keep_alive: false,
+            palette_preview: false,
+            fast_decode: false,
+            low_memory: false,
+            format: None,
+            no_alpha: false,
+            invert: false,
+            grayscale: false,
+            center: false,
+            anchor: None,
+            crop: None,
+            rotate: None,
+            flip_h: false,
+            flip_v: false,
+            bg_only: false,
+            wide_bg: false,
+            interpolate_palette: false,
+            palette: None,
+            linear_luma: false,
+            ascii_ramp: ASCII_RAMP.to_vec(),
+            ascii_invert: false,
+            threshold: 128,
+            dither: false,
+            seed: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0),
+            exposure: 0.0,
+            tonemap: ToneMapOperator::Reinhard,
+            brightness: 0,
+            contrast: 0.0,
+            gamma: 1.0,
+            background: None,
+        })
+    }
 }
 
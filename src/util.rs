@@ -1,8 +1,8 @@
 //! Module containing various utility functions.
 
 
-use std::iter;
-use image::Rgb;
+use self::super::Error;
+use image::{Rgb, Rgba};
 use std::ops::Index;
 
 
@@ -31,6 +31,64 @@ pub static PNG_MAGIC: &[u8] = &[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
 /// Source: [Wikipedia](https://en.wikipedia.org/wiki/List_of_file_signatures).
 pub static JPEG_MAGIC: &[u8] = &[0xFF, 0xD8, 0xFF, 0xE0];
 
+/// Magic number used for determining whether an image is a WebP, at the start of its RIFF container.
+///
+/// Source: [Wikipedia](https://en.wikipedia.org/wiki/List_of_file_signatures).
+pub static WEBP_MAGIC: &[u8] = b"RIFF";
+
+/// Form type following `WEBP_MAGIC` and the 4-byte RIFF chunk size in WebP images.
+///
+/// Source: [Wikipedia](https://en.wikipedia.org/wiki/List_of_file_signatures).
+pub static WEBP_FORM_MAGIC: &[u8] = b"WEBP";
+
+/// Magic number used for determining whether an image is a little-endian TIFF.
+///
+/// Source: [Wikipedia](https://en.wikipedia.org/wiki/List_of_file_signatures).
+pub static TIFF_MAGIC_LE: &[u8] = &[0x49, 0x49, 0x2A, 0x00];
+
+/// Magic number used for determining whether an image is a big-endian TIFF.
+///
+/// Source: [Wikipedia](https://en.wikipedia.org/wiki/List_of_file_signatures).
+pub static TIFF_MAGIC_BE: &[u8] = &[0x4D, 0x4D, 0x00, 0x2A];
+
+/// Magic number used for determining whether an image is a Radiance HDR.
+///
+/// Source: [Radiance File Formats](https://radsite.lbl.gov/radiance/refer/filefmts.pdf).
+pub static HDR_MAGIC: &[u8] = b"#?RADIANCE";
+
+/// Magic number used for determining whether an image is an OpenEXR.
+///
+/// Source: [Wikipedia](https://en.wikipedia.org/wiki/List_of_file_signatures).
+pub static EXR_MAGIC: &[u8] = &[0x76, 0x2F, 0x31, 0x01];
+
+/// Magic number used for determining whether an image is a QOI.
+///
+/// Source: [The Quite OK Image Format Specification](https://qoiformat.org/qoi-specification.pdf).
+pub static QOI_MAGIC: &[u8] = b"qoif";
+
+/// Type of the box AVIF images, as ISOBMFF files, start with, after its 4-byte size.
+///
+/// Source: [AVIF Specification](https://aomediacodec.github.io/av1-avif), section 9.
+pub static AVIF_FTYP_MAGIC: &[u8] = b"ftyp";
+
+/// Major brands following `AVIF_FTYP_MAGIC` in still and animated AVIF images.
+pub static AVIF_BRANDS: [&[u8]; 2] = [b"avif", b"avis"];
+
+/// Magic number used for determining whether a file is a PDF document.
+///
+/// Source: [Wikipedia](https://en.wikipedia.org/wiki/List_of_file_signatures).
+pub static PDF_MAGIC: &[u8] = &[0x25, 0x50, 0x44, 0x46];
+
+/// Signature at the very end of TGA 2.0 files, used for determining whether a file is TGA, as they have no leading magic.
+///
+/// Source: [Truevision TGA File Format Specification, Version 2.0](https://www.dca.fee.unicamp.br/~martino/disciplinas/ea978/tgaffs.pdf).
+pub static TGA_FOOTER_MAGIC: &[u8] = b"TRUEVISION-XFILE.\0";
+
+/// Identifier the TIFF structure holding a JPEG's EXIF metadata is prefixed with in its APP1 segment.
+///
+/// Source: [Exif Version 2.32](https://www.cipa.jp/std/documents/download_e.html?DC-008-Translation-2019-E), section 4.5.4.
+pub static EXIF_MAGIC: &[u8] = b"Exif\0\0";
+
 
 /// ANSI colours for a white-background terminal, in the same order as `ANSI_COLOUR_ESCAPES`.
 ///
@@ -103,6 +161,15 @@ pub static ANSI_COLOURS_BLACK_BG: [Rgb<u8>; 16] = [Rgb([0x00, 0x00, 0x00]),
                                                    Rgb([0x00, 0xFF, 0xFF]),
                                                    Rgb([0xFF, 0xFF, 0xFF])];
 
+/// The xterm 256-colour palette: the 16 basic colours, a 6×6×6 colour cube, and a 24-step grayscale ramp.
+///
+/// The basic colours are xterm's defaults, but terminals and themes commonly redefine them,
+/// so only the cube and the ramp, from index 16 onward, are meant to be matched against.
+pub static ANSI_COLOURS_256: [Rgb<u8>; 256] = ansi_colours_256();
+
+/// Index of the first colour in `ANSI_COLOURS_256` that doesn't depend on the terminal's theme.
+pub static ANSI_256_FIXED_START: usize = 16;
+
 /// ANSI background colour escapes.
 pub static ANSI_COLOUR_ESCAPES: [&str; 16] = ["\x1B[0;30m",
                                               "\x1B[0;31m",
@@ -124,38 +191,473 @@ pub static ANSI_COLOUR_ESCAPES: [&str; 16] = ["\x1B[0;30m",
 /// ANSI background colour escapes.
 pub static ANSI_BG_COLOUR_ESCAPES: [&str; 8] = ["\x1B[40m", "\x1B[41m", "\x1B[42m", "\x1B[43m", "\x1B[44m", "\x1B[45m", "\x1B[46m", "\x1B[47m"];
 
+/// ANSI 256-colour background escapes for the 16 basic colours, so backgrounds needn't be limited to the first 8.
+pub static ANSI_BG_COLOUR_ESCAPES_16: [&str; 16] = ["\x1B[48;5;0m",
+                                                    "\x1B[48;5;1m",
+                                                    "\x1B[48;5;2m",
+                                                    "\x1B[48;5;3m",
+                                                    "\x1B[48;5;4m",
+                                                    "\x1B[48;5;5m",
+                                                    "\x1B[48;5;6m",
+                                                    "\x1B[48;5;7m",
+                                                    "\x1B[48;5;8m",
+                                                    "\x1B[48;5;9m",
+                                                    "\x1B[48;5;10m",
+                                                    "\x1B[48;5;11m",
+                                                    "\x1B[48;5;12m",
+                                                    "\x1B[48;5;13m",
+                                                    "\x1B[48;5;14m",
+                                                    "\x1B[48;5;15m"];
+
+/// Blocks filling the lower `n` eighths of a cell, from empty to full.
+pub static LOWER_EIGHTH_BLOCKS: [&str; 9] = [" ", "\u{2581}", "\u{2582}", "\u{2583}", "\u{2584}", "\u{2585}", "\u{2586}", "\u{2587}", "\u{2588}"];
+
+/// Bits of the braille dots, offset from U+2800, at each (x, y) position of the 2x4 dot matrix, indexed `[y][x]`.
+pub static BRAILLE_DOTS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// Blocks filling the quadrants of a cell whose bits are set in the index, the upper left being 1, the upper right 2,
+/// the lower left 4, and the lower right 8.
+pub static QUADRANT_BLOCKS: [&str; 16] = [" ", "\u{2598}", "\u{259D}", "\u{2580}", "\u{2596}", "\u{258C}", "\u{259E}", "\u{259B}",
+                                         "\u{2597}", "\u{259A}", "\u{2590}", "\u{259C}", "\u{2584}", "\u{2599}", "\u{259F}", "\u{2588}"];
+
+/// Default ASCII art ramp, from the glyph for the least intense pixels to the one for the most.
+pub static ASCII_RAMP: [char; 8] = [' ', '.', ',', '-', '~', '+', '=', '@'];
+
+/// Line printed after output clipped to `--max-rows`.
+pub static TRUNCATED_INDICATOR: &str = "\u{2026} (truncated)";
+
 /// Reset ANSI attributes
 pub static ANSI_RESET_ATTRIBUTES: &str = "\x1B[0m";
 
+/// Hide the terminal cursor
+pub static ANSI_HIDE_CURSOR: &str = "\x1B[?25l";
+
+/// Show the terminal cursor
+pub static ANSI_SHOW_CURSOR: &str = "\x1B[?25h";
+
+
+/// Clear the screen and move the cursor to its top-left corner.
+pub static ANSI_CLEAR_SCREEN: &str = "\x1B[H\x1B[2J";
+
+/// XTSMGRAPHICS query for the largest sixel image the terminal can display, in pixels.
+pub static SIXEL_MAX_GEOMETRY_QUERY: &str = "\x1B[?2;4;0S";
+
+/// OSC 11 query for the terminal's background colour.
+pub static BACKGROUND_COLOUR_QUERY: &str = "\x1B]11;?\x07";
+
+/// Most base64 bytes to put in a single OSC 52 clipboard escape, as many terminals drop longer ones.
+pub static OSC52_MAX_PAYLOAD: usize = 100_000;
+
+
+/// Encode the specified bytes as standard, padded base64.
+///
+/// # Examples
+///
+/// ```
+/// # use imgcatr::util::base64_encode;
+/// assert_eq!(base64_encode(b""), "");
+/// assert_eq!(base64_encode(b"f"), "Zg==");
+/// assert_eq!(base64_encode(b"fo"), "Zm8=");
+/// assert_eq!(base64_encode(b"foo"), "Zm9v");
+/// assert_eq!(base64_encode(&[0xFB, 0xFF]), "+/8=");
+/// ```
+pub fn base64_encode(data: &[u8]) -> String {
+    static ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = (chunk[0] as u32) << 16 | (*chunk.get(1).unwrap_or(&0) as u32) << 8 | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Most base64 payload bytes the Kitty graphics protocol allows in a single escape.
+pub static KITTY_CHUNK_SIZE: usize = 4096;
+
+/// Get the width and height from a terminal's successful reply to `SIXEL_MAX_GEOMETRY_QUERY`.
+///
+/// # Examples
+///
+/// ```
+/// # use imgcatr::util::parse_sixel_geometry;
+/// assert_eq!(parse_sixel_geometry(b"\x1B[?2;0;1000;750S"), Some((1000, 750)));
+/// assert_eq!(parse_sixel_geometry(b"\x1B[?2;3;0S"), None);
+/// assert_eq!(parse_sixel_geometry(b""), None);
+/// ```
+pub fn parse_sixel_geometry(reply: &[u8]) -> Option<(u32, u32)> {
+    let start = reply.windows(7).position(|w| w == b"\x1B[?2;0;")? + 7;
+    let body = &reply[start..];
+    let body = std::str::from_utf8(&body[..body.iter().position(|&b| b == b'S')?]).ok()?;
+
+    let mut parts = body.split(';');
+    let width = parts.next()?.parse().ok()?;
+    let height = parts.next()?.parse().ok()?;
+    Some((width, height))
+}
+
+/// Get the colour from a terminal's reply to `BACKGROUND_COLOUR_QUERY`, like `\x1B]11;rgb:RRRR/GGGG/BBBB\x07`.
+///
+/// Each component may have 1 to 4 hex digits, and is scaled to 8 bits.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use imgcatr::util::parse_background_colour;
+/// # use image::Rgb;
+/// # fn main() {
+/// assert_eq!(parse_background_colour(b"\x1B]11;rgb:ffff/8080/0000\x07"), Some(Rgb([0xFF, 0x80, 0x00])));
+/// assert_eq!(parse_background_colour(b"\x1B]11;rgb:f/80/000\x1B\\"), Some(Rgb([0xFF, 0x80, 0x00])));
+/// assert_eq!(parse_background_colour(b"\x1B]11;rgb:ffff/ffff\x07"), None);
+/// assert_eq!(parse_background_colour(b""), None);
+/// # }
+/// ```
+pub fn parse_background_colour(reply: &[u8]) -> Option<Rgb<u8>> {
+    let start = reply.windows(9).position(|w| w == b"\x1B]11;rgb:")? + 9;
+    let body = &reply[start..];
+    let body = std::str::from_utf8(&body[..body.iter().position(|&b| b == b'\x07' || b == b'\x1B')?]).ok()?;
+
+    let mut components = [0; 3];
+    let mut parts = body.split('/');
+    for c in &mut components {
+        let part = parts.next().filter(|p| (1..=4).contains(&p.len()))?;
+        let max = (1u32 << (4 * part.len())) - 1;
+        *c = ((u32::from_str_radix(part, 16).ok()? * 0xFF + max / 2) / max) as u8;
+    }
+    parts.next().is_none().then_some(Rgb(components))
+}
+
+/// Parse a palette file's colours, one per line, for the simple formats to approximate to.
+///
+/// The format is picked by the first line: `GIMP Palette` for GIMP's `.gpl`, `JASC-PAL` for JASC's `.pal`,
+/// both with decimal `R G B` colours, and anything else for a plain list of `#RRGGBB` colours.
+/// Blank lines, the formats' headers, and GIMP's `#` comments and colour names are skipped,
+/// and there may be up to 16 colours, in the order of the terminal's palette.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use imgcatr::util::parse_palette;
+/// # use imgcatr::Error;
+/// # use image::Rgb;
+/// # fn main() {
+/// let colours = vec![Rgb([0x00, 0x00, 0x00]), Rgb([0xFF, 0x80, 0x00])];
+/// assert_eq!(parse_palette("#000000\n\n  #FF8000\n"), Ok(colours.clone()));
+/// assert_eq!(parse_palette("GIMP Palette\nName: Mine\nColumns: 2\n# Comment\n  0   0   0\tBlack\n255 128   0\tOrange\n"),
+///            Ok(colours.clone()));
+/// assert_eq!(parse_palette("JASC-PAL\r\n0100\r\n2\r\n0 0 0\r\n255 128 0\r\n"), Ok(colours));
+///
+/// assert_eq!(parse_palette("#000000\nff8000\n"),
+///            Err(Error::InvalidPalette("line 2, \"ff8000\", isn't a #RRGGBB colour".to_string())));
+/// assert_eq!(parse_palette("JASC-PAL\n0100\n1\n0 0 256\n"),
+///            Err(Error::InvalidPalette("line 4, \"0 0 256\", isn't an R G B colour".to_string())));
+/// assert!(parse_palette(&"#000000\n".repeat(17)).is_err());
+/// # }
+/// ```
+pub fn parse_palette(text: &str) -> Result<Vec<Rgb<u8>>, Error> {
+    let (header_lines, gimp, parse_colour, expected): (usize, bool, fn(&str) -> _, _) = match text.lines().next().map(str::trim) {
+        Some("GIMP Palette") => (1, true, parse_decimal_colour, "an R G B colour"),
+        // Followed by the version and colour count
+        Some("JASC-PAL") => (3, false, parse_decimal_colour, "an R G B colour"),
+        _ => (0, false, parse_hex_colour, "a #RRGGBB colour"),
+    };
+
+    let mut colours = Vec::new();
+    for (i, line) in text.lines().enumerate().skip(header_lines) {
+        let line = line.trim();
+        if line.is_empty() || (gimp && (line.starts_with('#') || line.split_once(':').is_some_and(|(key, _)| key.chars().all(char::is_alphabetic)))) {
+            continue;
+        }
+
+        colours.push(parse_colour(line).ok_or_else(|| Error::InvalidPalette(format!("line {}, \"{}\", isn't {}", i + 1, line, expected)))?);
+    }
+
+    if colours.len() > 16 {
+        return Err(Error::InvalidPalette(format!("{} colours given, but there are only 16", colours.len())));
+    }
+    Ok(colours)
+}
+
+/// Parse a `#RRGGBB` colour.
+fn parse_hex_colour(line: &str) -> Option<Rgb<u8>> {
+    let hex = line.strip_prefix('#').filter(|hex| hex.len() == 6 && hex.bytes().all(|b| b.is_ascii_hexdigit()))?;
+    let channel = |at: usize| u8::from_str_radix(&hex[at..at + 2], 16).unwrap();
+    Some(Rgb([channel(0), channel(2), channel(4)]))
+}
+
+/// Parse a colour of three whitespace-separated decimal channels, followed by anything, like GIMP's colour names.
+fn parse_decimal_colour(line: &str) -> Option<Rgb<u8>> {
+    let mut channels = [0; 3];
+    let mut parts = line.split_whitespace();
+    for c in &mut channels {
+        *c = parts.next()?.parse().ok()?;
+    }
+    Some(Rgb(channels))
+}
 
 /// Create a string consisting of `n` repetitions of `what`.
 ///
 /// # Examples
 ///
 /// ```
-/// # use termimage::util::mul_str;
+/// # use imgcatr::util::mul_str;
 /// assert_eq!(mul_str("Го! ", 3), "Го! Го! Го! ".to_string());
 /// ```
 pub fn mul_str(what: &str, n: usize) -> String {
-    iter::repeat(what).take(n).collect()
+    what.repeat(n)
 }
 
-/// Get the closest colour to the provided one out of the specified list of colours and retirn its index.
+/// Get the perceived brightness of a colour, as per the Rec. 601 luma coefficients.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use imgcatr::util::luminance;
+/// # use image::{Rgb, Rgba};
+/// # fn main() {
+/// assert_eq!(luminance(Rgb([0x00, 0x00, 0x00])), 0x00);
+/// assert_eq!(luminance(Rgb([0xFF, 0xFF, 0xFF])), 0xFF);
+/// assert_eq!(luminance(Rgb([0xFF, 0x00, 0x00])), 76);
+/// # }
+/// ```
+pub fn luminance(rgb: Rgb<u8>) -> u8 {
+    (0.299 * rgb[0] as f32 + 0.587 * rgb[1] as f32 + 0.114 * rgb[2] as f32).round() as u8
+}
+
+/// Composite a colour with straight (non-premultiplied) alpha, as used by `image`, over an opaque background.
+///
+/// Computes `fg * a + bg * (1 - a)` per channel, rounded to the nearest integer.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use imgcatr::util::blend_over;
+/// # use image::{Rgb, Rgba};
+/// # fn main() {
+/// assert_eq!(blend_over(Rgba([0xFF, 0x00, 0x80, 0xFF]), Rgb([0x00, 0xFF, 0x00])), Rgb([0xFF, 0x00, 0x80]));
+/// assert_eq!(blend_over(Rgba([0xFF, 0x00, 0x80, 0x00]), Rgb([0x00, 0xFF, 0x00])), Rgb([0x00, 0xFF, 0x00]));
+/// assert_eq!(blend_over(Rgba([0xFF, 0xFF, 0xFF, 0x80]), Rgb([0x00, 0x00, 0x00])), Rgb([0x80, 0x80, 0x80]));
+/// assert_eq!(blend_over(Rgba([0x00, 0x00, 0x00, 0x80]), Rgb([0xFF, 0xFF, 0xFF])), Rgb([0x7F, 0x7F, 0x7F]));
+/// assert_eq!(blend_over(Rgba([0x64, 0x64, 0x64, 0x40]), Rgb([0xC8, 0xC8, 0xC8])), Rgb([0xAF, 0xAF, 0xAF]));
+/// # }
+/// ```
+pub fn blend_over(fg: Rgba<u8>, bg: Rgb<u8>) -> Rgb<u8> {
+    let a = fg[3] as u32;
+    let blend = |f: u8, b: u8| ((f as u32 * a + b as u32 * (255 - a) + 127) / 255) as u8;
+    Rgb([blend(fg[0], bg[0]), blend(fg[1], bg[1]), blend(fg[2], bg[2])])
+}
+
+/// Linearly interpolate between two colours, alpha included, `t` of the way from `from` to `to`.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use imgcatr::util::blend;
+/// # use image::Rgba;
+/// # fn main() {
+/// let (from, to) = (Rgba([0x00, 0x40, 0xFF, 0xFF]), Rgba([0xFF, 0x40, 0x00, 0x00]));
+/// assert_eq!(blend(from, to, 0.0), from);
+/// assert_eq!(blend(from, to, 1.0), to);
+/// assert_eq!(blend(from, to, 0.5), Rgba([0x80, 0x40, 0x80, 0x80]));
+/// # }
+/// ```
+pub fn blend(from: Rgba<u8>, to: Rgba<u8>, t: f32) -> Rgba<u8> {
+    let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    Rgba([lerp(from[0], to[0]), lerp(from[1], to[1]), lerp(from[2], to[2]), lerp(from[3], to[3])])
+}
+
+/// Composite a colour with premultiplied alpha over an opaque background.
+///
+/// Computes `fg + bg * (1 - a)` per channel, rounded to the nearest integer.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use imgcatr::util::blend_over_premultiplied;
+/// # use image::{Rgb, Rgba};
+/// # fn main() {
+/// assert_eq!(blend_over_premultiplied(Rgba([0x80, 0x80, 0x80, 0x80]), Rgb([0x00, 0x00, 0x00])), Rgb([0x80, 0x80, 0x80]));
+/// assert_eq!(blend_over_premultiplied(Rgba([0x00, 0x00, 0x00, 0x80]), Rgb([0xFF, 0xFF, 0xFF])), Rgb([0x7F, 0x7F, 0x7F]));
+/// assert_eq!(blend_over_premultiplied(Rgba([0x00, 0x00, 0x00, 0x00]), Rgb([0x12, 0x34, 0x56])), Rgb([0x12, 0x34, 0x56]));
+/// # }
+/// ```
+pub fn blend_over_premultiplied(fg: Rgba<u8>, bg: Rgb<u8>) -> Rgb<u8> {
+    let a = fg[3] as u32;
+    let blend = |f: u8, b: u8| (f as u32 + (b as u32 * (255 - a) + 127) / 255).min(255) as u8;
+    Rgb([blend(fg[0], bg[0]), blend(fg[1], bg[1]), blend(fg[2], bg[2])])
+}
+
+/// Encode a linear-light intensity in `[0, 1]` with the sRGB transfer function, clamping it if need be.
+///
+/// # Examples
+///
+/// ```
+/// # use imgcatr::util::srgb_encode;
+/// assert_eq!(srgb_encode(0.0), 0);
+/// assert_eq!(srgb_encode(0.5), 188);
+/// assert_eq!(srgb_encode(1.0), 255);
+/// assert_eq!(srgb_encode(7.0), 255);
+/// ```
+pub fn srgb_encode(v: f32) -> u8 {
+    let v = v.clamp(0.0, 1.0);
+    let encoded = if v <= 0.0031308 {
+        12.92 * v
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    };
+    (encoded * 255.0).round() as u8
+}
+
+/// Decode an sRGB-encoded channel into a linear-light intensity in `[0, 1]`, the inverse of `srgb_encode()`.
+///
+/// # Examples
+///
+/// ```
+/// # use imgcatr::util::{srgb_decode, srgb_encode};
+/// assert_eq!(srgb_decode(0), 0.0);
+/// assert_eq!(srgb_decode(255), 1.0);
+/// assert_eq!(srgb_encode(srgb_decode(188)), 188);
+/// ```
+pub fn srgb_decode(v: u8) -> f32 {
+    let v = v as f32 / 255.0;
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Get the luminance of the specified colour, averaged in linear light and encoded back with sRGB's transfer function.
+///
+/// Slower than `luminance()`, but mixes channels as the eye does, so saturated colours don't come out too dark.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use imgcatr::util::{luminance, luminance_linear};
+/// # use image::Rgb;
+/// # fn main() {
+/// assert_eq!(luminance_linear(Rgb([0x00, 0x00, 0x00])), 0x00);
+/// assert_eq!(luminance_linear(Rgb([0xFF, 0xFF, 0xFF])), 0xFF);
+/// assert_eq!(luminance_linear(Rgb([0x80, 0x80, 0x80])), 0x80);
+/// assert!(luminance_linear(Rgb([0xFF, 0x00, 0x00])) > luminance(Rgb([0xFF, 0x00, 0x00])));
+/// # }
+/// ```
+pub fn luminance_linear(rgb: Rgb<u8>) -> u8 {
+    srgb_encode(0.2126 * srgb_decode(rgb[0]) + 0.7152 * srgb_decode(rgb[1]) + 0.0722 * srgb_decode(rgb[2]))
+}
+
+/// Turn detected terminal dimensions into the default output size, leaving a line for the prompt.
+///
+/// Detached sessions can report zero rows or columns, so those are treated the same as failed detection.
+///
+/// # Examples
+///
+/// ```
+/// # use imgcatr::util::default_output_size;
+/// assert_eq!(default_output_size(Some((80, 25))), Some((80, 24)));
+/// assert_eq!(default_output_size(Some((0, 25))), None);
+/// assert_eq!(default_output_size(Some((80, 0))), None);
+/// assert_eq!(default_output_size(Some((80, 1))), None);
+/// assert_eq!(default_output_size(None), None);
+/// ```
+pub fn default_output_size(dims: Option<(usize, usize)>) -> Option<(u32, u32)> {
+    match dims {
+        Some((w, h)) if w > 0 && h > 1 => Some((w as u32, h as u32 - 1)),
+        _ => None,
+    }
+}
+
+/// Get the perceptual distance between the two specified colours.
 ///
 /// The formula is the last one from the
 /// [Euclidean section in the Color difference article on Wikipedia](https://en.wikipedia.org/wiki/Color_difference#Euclidean)
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use imgcatr::util::colour_distance;
+/// # use image::Rgb;
+/// # fn main() {
+/// assert_eq!(colour_distance(Rgb([0x12, 0x34, 0x56]), &Rgb([0x12, 0x34, 0x56])), 0.0);
+/// assert!(colour_distance(Rgb([0x00, 0x00, 0x00]), &Rgb([0x00, 0xFF, 0x00])) >
+///         colour_distance(Rgb([0x00, 0x00, 0x00]), &Rgb([0x00, 0x00, 0xFF])));
+/// # }
+/// ```
+pub fn colour_distance<P: Index<usize, Output = u8>>(from: Rgb<u8>, to: &P) -> f32 {
+    let r = (to[0] as f32 + from[0] as f32) / 2.0;
+    ((2.0 + (r / 256.0)) * (to[0] as f32 - from[0] as f32).powi(2) + 4.0 * (to[1] as f32 - from[1] as f32).powi(2) +
+     (2.0 + ((255.0 - r) / 256.0)) * (to[2] as f32 - from[2] as f32).powi(2))
+        .sqrt()
+}
+
+/// Get the closest colour to the provided one out of the specified list of colours and retirn its index.
+///
+/// Colours are compared with `colour_distance()`.
 pub fn closest_colour<P: Index<usize, Output = u8>>(to: Rgb<u8>, out_of: &[P]) -> usize {
-    let mut diffs = out_of.iter()
+    out_of.iter()
         .enumerate()
-        .map(|(i, rgb)| {
-            let r = (rgb[0] as f32 + to[0] as f32) / 2.0;
-            ((2.0 + (r / 256.0)) * (rgb[0] as f32 - to[0] as f32).powi(2) + 4.0 * (rgb[1] as f32 - to[1] as f32).powi(2) +
-             (2.0 + ((255.0 - r) / 256.0)) * (rgb[2] as f32 - to[2] as f32).powi(2),
-             i)
-        })
-        .collect::<Vec<_>>();
-    diffs.sort_by(|&(lhs_diff, _), &(rhs_diff, _)| lhs_diff.partial_cmp(&rhs_diff).unwrap());
-    diffs[0].1
+        .map(|(i, rgb)| (colour_distance(to, rgb), i))
+        .min_by(|&(lhs_diff, _), &(rhs_diff, _)| lhs_diff.partial_cmp(&rhs_diff).unwrap())
+        .unwrap()
+        .1
+}
+
+/// Table of `closest_colour()` indices into a palette, by the top 5 bits of the red, green, and blue components, in that order.
+pub type ColourLut = [[[u8; 32]; 32]; 32];
+
+/// Precompute `closest_colour()` out of the specified palette of at most 256 colours for each 8x8x8 block of RGB space,
+/// so that approximating a pixel is just `lut[r >> 3][g >> 3][b >> 3]`.
+///
+/// Each block is approximated by its centre, so lookups may be a quantisation step off from `closest_colour()`,
+/// but building the table pays off once many more pixels than its 32768 entries are approximated.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate image;
+/// # extern crate imgcatr;
+/// # use imgcatr::util::{ANSI_COLOURS_BLACK_BG, build_colour_lut};
+/// # fn main() {
+/// let lut = build_colour_lut(&ANSI_COLOURS_BLACK_BG);
+/// assert_eq!(lut[0xCD >> 3][0x00 >> 3][0x00 >> 3], 1);
+/// assert_eq!(lut[0xFF >> 3][0xFF >> 3][0xFF >> 3], 15);
+/// # }
+/// ```
+pub fn build_colour_lut<P: Index<usize, Output = u8>>(palette: &[P]) -> Box<ColourLut> {
+    assert!(palette.len() <= 256, "colour lookup tables only index up to 256 colours");
+
+    let mut lut = Box::new([[[0; 32]; 32]; 32]);
+    for (r, plane) in lut.iter_mut().enumerate() {
+        for (g, row) in plane.iter_mut().enumerate() {
+            for (b, idx) in row.iter_mut().enumerate() {
+                *idx = closest_colour(Rgb([(r << 3 | 4) as u8, (g << 3 | 4) as u8, (b << 3 | 4) as u8]), palette) as u8;
+            }
+        }
+    }
+    lut
 }
 
 /// Get the background colour set for the specified foregournd colour set
@@ -163,3 +665,40 @@ pub fn closest_colour<P: Index<usize, Output = u8>>(to: Rgb<u8>, out_of: &[P]) -
 pub fn bg_colours_for<C: Index<usize, Output = u8>>(foreground_colours: &[C]) -> &[C] {
     &foreground_colours[0..8]
 }
+
+
+const fn ansi_colours_256() -> [Rgb<u8>; 256] {
+    const BASIC: [[u8; 3]; 16] = [[0x00, 0x00, 0x00],
+                                  [0xCD, 0x00, 0x00],
+                                  [0x00, 0xCD, 0x00],
+                                  [0xCD, 0xCD, 0x00],
+                                  [0x00, 0x00, 0xEE],
+                                  [0xCD, 0x00, 0xCD],
+                                  [0x00, 0xCD, 0xCD],
+                                  [0xE5, 0xE5, 0xE5],
+                                  [0x7F, 0x7F, 0x7F],
+                                  [0xFF, 0x00, 0x00],
+                                  [0x00, 0xFF, 0x00],
+                                  [0xFF, 0xFF, 0x00],
+                                  [0x5C, 0x5C, 0xFF],
+                                  [0xFF, 0x00, 0xFF],
+                                  [0x00, 0xFF, 0xFF],
+                                  [0xFF, 0xFF, 0xFF]];
+    const CUBE_LEVELS: [u8; 6] = [0x00, 0x5F, 0x87, 0xAF, 0xD7, 0xFF];
+
+    let mut palette = [Rgb([0, 0, 0]); 256];
+    let mut i = 0;
+    while i < 256 {
+        palette[i] = Rgb(if i < 16 {
+            BASIC[i]
+        } else if i < 232 {
+            let c = i - 16;
+            [CUBE_LEVELS[c / 36], CUBE_LEVELS[c / 6 % 6], CUBE_LEVELS[c % 6]]
+        } else {
+            let v = 8 + (i - 232) as u8 * 10;
+            [v, v, v]
+        });
+        i += 1;
+    }
+    palette
+}